@@ -0,0 +1,235 @@
+// Minimal sticker picker widget for maunium stickerpacks created by mstickereditor.
+// Reads `packs/index.json` and sends the selected sticker using the widget api.
+"use strict";
+
+const params = new URLSearchParams(window.location.search);
+const widgetId = params.get("widgetId");
+// MSC4039: download authenticated media through the client, since the widget has no access token
+const DOWNLOAD_FILE = "org.matrix.msc4039.download_file";
+const RECENT_KEY = "mstickereditor.recent";
+const MAX_RECENT = 16;
+
+// origin of the client, pinned at its first `capabilities` request; all other origins are ignored
+let clientOrigin = null;
+let canDownload = false;
+let homeserverUrl = null;
+let requestCounter = 0;
+const pendingRequests = new Map();
+
+function sendToClient(action, data) {
+	if (clientOrigin === null) {
+		return Promise.reject(new Error("the client has not connected to the widget"));
+	}
+	const requestId = `mstickereditor-${Date.now()}-${requestCounter++}`;
+	return new Promise((resolve, reject) => {
+		pendingRequests.set(requestId, { resolve, reject });
+		window.parent.postMessage({ api: "fromWidget", action, widgetId, requestId, data }, clientOrigin);
+	});
+}
+
+window.addEventListener("message", event => {
+	const message = event.data;
+	if (event.source !== window.parent || !message || typeof message !== "object") {
+		return;
+	}
+	if (clientOrigin === null && message.api === "toWidget" && message.action === "capabilities") {
+		clientOrigin = event.origin;
+	}
+	if (event.origin !== clientOrigin) {
+		return;
+	}
+	if (message.api === "fromWidget") {
+		// response of the client to a request of the widget
+		const pending = pendingRequests.get(message.requestId);
+		if (pending) {
+			pendingRequests.delete(message.requestId);
+			const error = message.response && message.response.error;
+			if (error) {
+				pending.reject(new Error(error.message));
+			} else {
+				pending.resolve(message.response);
+			}
+		}
+		return;
+	}
+	if (message.api !== "toWidget") {
+		return;
+	}
+	let response = {};
+	if (message.action === "capabilities") {
+		response = { capabilities: ["m.sticker", DOWNLOAD_FILE] };
+	} else if (message.action === "notify_capabilities") {
+		canDownload = ((message.data && message.data.approved) || []).includes(DOWNLOAD_FILE);
+		if (canDownload) {
+			// images, which were already loaded without authentication
+			document.querySelectorAll("img[data-source=unauthenticated]").forEach(loadImage);
+		}
+	}
+	window.parent.postMessage({ ...message, response }, clientOrigin);
+});
+
+// legacy media endpoint, only works if the homeserver still serves unauthenticated media
+function unauthenticatedThumbnailUrl(mxc) {
+	const [serverName, mediaId] = mxc.slice("mxc://".length).split("/");
+	return `${homeserverUrl}/_matrix/media/v3/thumbnail/${serverName}/${mediaId}?height=128&width=128&method=scale`;
+}
+
+async function loadImage(image) {
+	const mxc = image.dataset.mxc;
+	if (canDownload) {
+		try {
+			const response = await sendToClient(DOWNLOAD_FILE, { content_uri: mxc });
+			image.src = URL.createObjectURL(new Blob([response.file]));
+			image.dataset.source = "client";
+			return;
+		} catch (error) {
+			console.warn(`failed to download ${mxc} through the client`, error);
+		}
+	}
+	image.src = unauthenticatedThumbnailUrl(mxc);
+	image.dataset.source = "unauthenticated";
+}
+
+// load images only when they become visible
+const imageObserver = new IntersectionObserver(entries => {
+	for (const entry of entries.filter(entry => entry.isIntersecting)) {
+		imageObserver.unobserve(entry.target);
+		loadImage(entry.target);
+	}
+});
+
+function createImage(mxc, alt) {
+	const image = document.createElement("img");
+	image.alt = alt;
+	image.dataset.mxc = mxc;
+	imageObserver.observe(image);
+	return image;
+}
+
+function loadRecent() {
+	try {
+		return JSON.parse(localStorage.getItem(RECENT_KEY)) || [];
+	} catch {
+		return [];
+	}
+}
+
+function addRecent(sticker) {
+	const recent = [sticker, ...loadRecent().filter(other => other.url !== sticker.url)].slice(0, MAX_RECENT);
+	try {
+		localStorage.setItem(RECENT_KEY, JSON.stringify(recent));
+	} catch {
+		// storage is not available, for example at private windows
+	}
+	renderRecent();
+}
+
+function sendSticker(sticker) {
+	const content = {
+		body: sticker.body,
+		url: sticker.url,
+		info: sticker.info,
+		msgtype: sticker.msgtype
+	};
+	sendToClient("m.sticker", { name: sticker.body, content }).catch(error => console.error("failed to send sticker", error));
+	addRecent(sticker);
+}
+
+// text a sticker can be found by
+function searchText(sticker) {
+	// packs imported by mstickereditor store the emoji at their own key, packs of the bridge only at the maunium key
+	const tgInfo = sticker["dev.luckyturtle.mstickereditor.tg_sticker"];
+	const tgSticker = sticker["net.maunium.telegram.sticker"];
+	let emoji = sticker["dev.luckyturtle.mstickereditor.emoji"] || [];
+	if (tgInfo && tgInfo.emoji && tgInfo.emoji.length) {
+		emoji = tgInfo.emoji;
+	} else if (tgSticker && tgSticker.emoticons) {
+		emoji = tgSticker.emoticons;
+	}
+	const emoticon = sticker["dev.luckyturtle.mstickereditor.emoticon"] || "";
+	return `${sticker.body} ${emoticon} ${emoji.join(" ")}`.toLowerCase();
+}
+
+function renderStickers(stickers) {
+	const container = document.createElement("div");
+	container.className = "stickers";
+	for (const sticker of stickers) {
+		const button = document.createElement("button");
+		button.className = "sticker";
+		button.title = sticker.body;
+		button.dataset.search = searchText(sticker);
+		button.addEventListener("click", () => sendSticker(sticker));
+		button.appendChild(createImage(sticker.info.thumbnail_url, sticker.body));
+		container.appendChild(button);
+	}
+	return container;
+}
+
+function renderSection(id, title, stickers) {
+	const section = document.createElement("section");
+	section.id = id;
+	const heading = document.createElement("h2");
+	heading.textContent = title;
+	section.appendChild(heading);
+	section.appendChild(renderStickers(stickers));
+	return section;
+}
+
+function renderRecent() {
+	const recent = loadRecent();
+	const old = document.getElementById("recent");
+	const section = renderSection("recent", "Recently used", recent);
+	section.hidden = recent.length === 0;
+	if (old) {
+		old.replaceWith(section);
+	} else {
+		document.getElementById("packs").prepend(section);
+	}
+	filter();
+}
+
+function renderPack(pack, index) {
+	const section = renderSection(`pack-${index}`, pack.title, pack.stickers);
+	const link = document.createElement("a");
+	link.href = `#${section.id}`;
+	link.title = pack.title;
+	const avatar = pack["dev.luckyturtle.mstickereditor.avatar"];
+	if (avatar || pack.stickers.length > 0) {
+		link.appendChild(createImage(avatar ? avatar.url : pack.stickers[0].info.thumbnail_url, pack.title));
+	}
+	document.getElementById("pack-list").appendChild(link);
+	return section;
+}
+
+// show only the stickers matching the search
+function filter() {
+	const query = document.getElementById("search").value.trim().toLowerCase();
+	for (const section of document.querySelectorAll("#packs section")) {
+		let visible = 0;
+		for (const sticker of section.querySelectorAll(".sticker")) {
+			sticker.hidden = query !== "" && !sticker.dataset.search.includes(query);
+			visible += sticker.hidden ? 0 : 1;
+		}
+		section.classList.toggle("empty", visible === 0);
+	}
+}
+
+async function load() {
+	const main = document.getElementById("packs");
+	document.getElementById("search").addEventListener("input", filter);
+	try {
+		const index = await (await fetch("packs/index.json")).json();
+		homeserverUrl = index.homeserver_url;
+		const packs = await Promise.all(index.packs.map(async file => (await fetch(`packs/${file}`)).json()));
+		main.replaceChildren(...packs.map(renderPack));
+		renderRecent();
+	} catch (error) {
+		main.innerHTML = "";
+		const message = document.createElement("p");
+		message.className = "message";
+		message.textContent = `Failed to load stickers: ${error}`;
+		main.appendChild(message);
+	}
+}
+
+load();
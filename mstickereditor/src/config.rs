@@ -0,0 +1,276 @@
+use crate::{database::DatabaseConfig, PROJECT_DIRS};
+use anyhow::{anyhow, bail, Context};
+use clap::Args;
+use mstickerlib::{database::DynDatabase, image::AnimationFormat, matrix, tg};
+use serde::Deserialize;
+use std::{
+	collections::BTreeMap,
+	env, fs,
+	path::PathBuf,
+	process::{Command, Stdio},
+	sync::OnceLock
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// global options, which affect how the config is loaded
+#[derive(Debug, Args)]
+pub struct ConfigOpt {
+	/// use the named profile from `[profiles.<PROFILE>]` of the config file
+	#[clap(long, global = true)]
+	profile: Option<String>,
+
+	/// path of the config file [default: ~/.config/mstickereditor/config.toml]
+	#[clap(long = "config", value_name = "PATH", global = true, env = "MSTICKEREDITOR_CONFIG")]
+	config_file: Option<PathBuf>
+}
+
+/// set by `main` after parsing the command line
+pub static CONFIG_OPT: OnceLock<ConfigOpt> = OnceLock::new();
+
+/// path of the config file, which should be used
+pub fn config_file_path() -> PathBuf {
+	CONFIG_OPT
+		.get()
+		.and_then(|opt| opt.config_file.clone())
+		.unwrap_or_else(|| PROJECT_DIRS.config_dir().join(CONFIG_FILE))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TelegramSection {
+	bot_key: Option<String>,
+	bot_key_file: Option<PathBuf>,
+	bot_key_command: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixSection {
+	homeserver_url: String,
+	user: String,
+	access_token: Option<String>,
+	access_token_file: Option<PathBuf>,
+	access_token_command: Option<String>
+}
+
+/// Sections of the config file, which can be set globally and overwritten by a profile.
+#[derive(Debug, Default, Deserialize)]
+struct Sections {
+	telegram: Option<TelegramSection>,
+	matrix: Option<MatrixSection>,
+	sticker: Option<AnimationFormat>,
+	database: Option<DatabaseConfig>
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfigFile {
+	#[serde(flatten)]
+	sections: Sections,
+	#[serde(default)]
+	profiles: BTreeMap<String, Sections>
+}
+
+/// Config of the selected profile.
+#[derive(Debug)]
+pub struct Config {
+	pub telegram: tg::Config,
+	pub matrix: matrix::Config,
+	pub sticker: AnimationFormat,
+	pub database: DatabaseConfig,
+	/// data dir of the selected profile,
+	/// so each profile has its own database by default
+	pub data_dir: PathBuf
+}
+
+impl Config {
+	/// Check that the matrix account can be used, skipped at a dry run, which does not upload anything.
+	pub async fn check_matrix(&self, dryrun: bool) -> anyhow::Result<()> {
+		if !dryrun {
+			matrix::whoami(&self.matrix)
+				.await
+				.context("Error connecting to Matrix homeserver")?;
+		}
+		Ok(())
+	}
+
+	/// Settings for importing stickers, with the animation format of the config file.
+	/// Further settings can be changed at the returned value.
+	pub fn import_config<'a>(
+		&self,
+		database: Option<&'a dyn DynDatabase>,
+		dryrun: bool
+	) -> tg::ImportConfig<'a, dyn DynDatabase + 'a> {
+		let mut import_config = tg::ImportConfig::<dyn DynDatabase>::default();
+		import_config.database = database;
+		import_config.dry_run = dryrun;
+		import_config.animation_format = self.sticker;
+		import_config
+	}
+}
+
+/// Get a secret from exactly one of `<key>`, `<key>_file` and `<key>_command` of the config file.
+/// The environment variable `env` is only used, if none of them is set,
+/// so it does not overwrite the secrets of profiles.
+fn secret(
+	key: &str,
+	env: &str,
+	value: Option<String>,
+	file: Option<PathBuf>,
+	command: Option<String>
+) -> anyhow::Result<String> {
+	match (value, file, command) {
+		(Some(value), None, None) => Ok(value),
+		(None, Some(file), None) => Ok(fs::read_to_string(&file)
+			.with_context(|| format!("Failed to read `{key}_file` {file:?}"))?
+			.trim_end()
+			.to_owned()),
+		(None, None, Some(command)) => {
+			let (shell, arg) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+			let output = Command::new(shell)
+				.arg(arg)
+				.arg(&command)
+				.stdin(Stdio::inherit())
+				.stderr(Stdio::inherit())
+				.output()
+				.with_context(|| format!("Failed to run `{key}_command` {command:?}"))?;
+			if !output.status.success() {
+				bail!("`{key}_command` {command:?} has failed with {}", output.status);
+			}
+			Ok(String::from_utf8(output.stdout)
+				.with_context(|| format!("Output of `{key}_command` {command:?} is not valid utf8"))?
+				.trim_end()
+				.to_owned())
+		},
+		(None, None, None) => {
+			if let Ok(value) = env::var(env) {
+				return Ok(value);
+			}
+			bail!("`{key}` is not set. Use `{key}`, `{key}_file`, `{key}_command` or the environment variable {env}")
+		},
+		_ => bail!("Only one of `{key}`, `{key}_file` and `{key}_command` can be set")
+	}
+}
+
+/// Check that a profile name can be used as directory name.
+fn check_profile_name(profile: &str) -> anyhow::Result<()> {
+	if profile.is_empty() || profile == "." || profile == ".." || profile.contains(['/', '\\']) {
+		bail!("invalid profile name {profile:?}");
+	}
+	Ok(())
+}
+
+impl ConfigFile {
+	/// merge the sections of `profile` with the global sections
+	fn into_config(self, profile: Option<&str>) -> anyhow::Result<Config> {
+		let Self { sections, mut profiles } = self;
+		let (profile_sections, data_dir) = match profile {
+			None => (Sections::default(), PROJECT_DIRS.data_dir().to_owned()),
+			Some(profile) => {
+				check_profile_name(profile)?;
+				(
+					profiles
+						.remove(profile)
+						.ok_or_else(|| anyhow!("profile {profile:?} is not defined at the config file"))?,
+					PROJECT_DIRS.data_dir().join("profiles").join(profile)
+				)
+			}
+		};
+		let telegram = profile_sections.telegram.or(sections.telegram).unwrap_or_default();
+		let matrix = profile_sections.matrix.or(sections.matrix).ok_or_else(|| match profile {
+			None => anyhow!("section [matrix] is missing at the config file"),
+			Some(profile) => anyhow!("neither [matrix] nor [profiles.{profile}.matrix] is set at the config file")
+		})?;
+		Ok(Config {
+			telegram: tg::Config {
+				bot_key: secret(
+					"bot_key",
+					"MSTICKEREDITOR_TELEGRAM_BOT_KEY",
+					telegram.bot_key,
+					telegram.bot_key_file,
+					telegram.bot_key_command
+				)?
+			},
+			matrix: matrix::Config {
+				homeserver_url: matrix.homeserver_url,
+				user: matrix.user,
+				access_token: secret(
+					"access_token",
+					"MSTICKEREDITOR_MATRIX_ACCESS_TOKEN",
+					matrix.access_token,
+					matrix.access_token_file,
+					matrix.access_token_command
+				)?
+			},
+			sticker: profile_sections.sticker.or(sections.sticker).unwrap_or_default(),
+			database: profile_sections.database.or(sections.database).unwrap_or_default(),
+			data_dir
+		})
+	}
+}
+
+pub fn load_config_file() -> anyhow::Result<Config> {
+	load_profile(CONFIG_OPT.get().and_then(|opt| opt.profile.as_deref()))
+}
+
+/// load the config of `profile`, independent of the `--profile` flag
+pub fn load_profile(profile: Option<&str>) -> anyhow::Result<Config> {
+	let path = config_file_path();
+	let config_file: ConfigFile = toml::from_str(
+		&fs::read_to_string(&path).with_context(|| format!("Failed to open config file {:?}", path.to_string_lossy()))?
+	)
+	.with_context(|| format!("Failed to prase config file {:?}", path.to_string_lossy()))?;
+	config_file
+		.into_config(profile)
+		.with_context(|| format!("Invalid config file {:?}", path.to_string_lossy()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ConfigFile;
+
+	#[test]
+	fn profile_overwrites_sections() {
+		let config_file: ConfigFile = toml::from_str(
+			r#"
+			[telegram]
+			bot_key = "global"
+			[matrix]
+			user = "@user:example.org"
+			homeserver_url = "https://example.org"
+			access_token = "global"
+			[sticker]
+			animation_format = "webp"
+			[profiles.work.matrix]
+			user = "@bot:example.com"
+			homeserver_url = "https://example.com"
+			access_token = "work"
+			[profiles.work.database]
+			backend = "none"
+			"#
+		)
+		.unwrap();
+		let config = config_file.into_config(Some("work")).unwrap();
+		assert_eq!(config.telegram.bot_key, "global");
+		assert_eq!(config.matrix.access_token, "work");
+		assert!(config.data_dir.ends_with("profiles/work"));
+	}
+
+	#[test]
+	fn invalid_profile_name() {
+		for profile in ["", "..", "a/b", "a\\b"] {
+			let config_file: ConfigFile = toml::from_str(&format!(
+				r#"
+				[matrix]
+				user = "@user:example.org"
+				homeserver_url = "https://example.org"
+				access_token = "global"
+				[profiles.{profile:?}.matrix]
+				user = "@bot:example.com"
+				homeserver_url = "https://example.com"
+				access_token = "work"
+				"#
+			))
+			.unwrap();
+			assert!(config_file.into_config(Some(profile)).is_err(), "{profile:?}");
+		}
+	}
+}
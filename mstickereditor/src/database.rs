@@ -0,0 +1,69 @@
+use anyhow::Context;
+#[cfg(feature = "sqlite")]
+use mstickerlib::database::SqliteDatabase;
+use mstickerlib::database::{DynDatabase, FileDatabase};
+use serde::Deserialize;
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
+
+/// Database used to avoid duplicate uploads of the same file.
+/// Can be choosen at the `[database]` section of the config file.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum DatabaseConfig {
+	File {
+		path: Option<PathBuf>
+	},
+	#[cfg(feature = "sqlite")]
+	Sqlite {
+		path: Option<PathBuf>
+	},
+	/// do not track uploaded files
+	None
+}
+
+impl Default for DatabaseConfig {
+	fn default() -> Self {
+		Self::File { path: None }
+	}
+}
+
+impl DatabaseConfig {
+	/// open the configured database, return `None` if no database should be used.
+	/// If no path is set, the database is stored inside `data_dir`.
+	/// If the database is used by another process, wait until it is released.
+	pub async fn open(&self, data_dir: &Path) -> anyhow::Result<Option<Box<dyn DynDatabase>>> {
+		self.open_with(data_dir, true).await
+	}
+
+	/// like [DatabaseConfig::open], but fail instead of waiting, if the database is used by another process
+	pub async fn open_nonblocking(&self, data_dir: &Path) -> anyhow::Result<Option<Box<dyn DynDatabase>>> {
+		self.open_with(data_dir, false).await
+	}
+
+	async fn open_with(&self, data_dir: &Path, wait: bool) -> anyhow::Result<Option<Box<dyn DynDatabase>>> {
+		if !matches!(self, Self::None) {
+			fs::create_dir_all(data_dir).with_context(|| format!("Cannot create data dir {}", data_dir.display()))?;
+		}
+		Ok(match self {
+			Self::File { path } => {
+				let path = path.clone().unwrap_or_else(|| data_dir.join("uploads"));
+				let database = match wait {
+					true => FileDatabase::new(&path).await,
+					false => FileDatabase::try_new(&path).await
+				}
+				.with_context(|| format!("failed to open database {path:?}"))?;
+				Some(Box::new(database))
+			},
+			#[cfg(feature = "sqlite")]
+			Self::Sqlite { path } => {
+				let path = path.clone().unwrap_or_else(|| data_dir.join("uploads.sqlite"));
+				let database = SqliteDatabase::new(&path).with_context(|| format!("failed to open database {path:?}"))?;
+				Some(Box::new(database))
+			},
+			Self::None => None
+		})
+	}
+}
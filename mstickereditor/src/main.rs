@@ -1,23 +1,23 @@
 #![warn(rust_2018_idioms)]
 #![forbid(unsafe_code)]
 
-use anyhow::Context;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use directories::ProjectDirs;
 use log::error;
-use mstickerlib::{image::AnimationFormat, matrix, tg};
+use mstickerlib::matrix;
 use once_cell::sync::Lazy;
-use serde::Deserialize;
-use std::{fs, path::PathBuf, process::exit};
+use std::{fs, process::exit};
 
+mod config;
+pub use config::load_config_file;
+mod database;
+mod pack_file;
 mod sub_commands;
 use sub_commands::*;
 
-const CONFIG_FILE: &str = "config.toml";
 const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 static PROJECT_DIRS: Lazy<ProjectDirs> =
 	Lazy::new(|| ProjectDirs::from("dev", "luckyturtle", CARGO_PKG_NAME).expect("failed to get project dirs"));
-static DATABASE_FILE: Lazy<PathBuf> = Lazy::new(|| PROJECT_DIRS.data_dir().join("uploads"));
 
 pub fn new_current_thread_runtime() -> Result<tokio::runtime::Runtime, std::io::Error> {
 	tokio::runtime::Builder::new_current_thread()
@@ -26,36 +26,43 @@ pub fn new_current_thread_runtime() -> Result<tokio::runtime::Runtime, std::io::
 		.build()
 }
 
-pub fn load_config_file() -> anyhow::Result<Config> {
-	let config: Config = toml::from_str(&fs::read_to_string(PROJECT_DIRS.config_dir().join(CONFIG_FILE)).with_context(
-		|| {
-			format!(
-				"Failed to open config file {:?}",
-				PROJECT_DIRS.config_dir().join(CONFIG_FILE).to_string_lossy()
-			)
-		}
-	)?)
-	.with_context(|| {
-		format!(
-			"Failed to prase config file {:?}",
-			PROJECT_DIRS.config_dir().join(CONFIG_FILE).to_string_lossy()
-		)
-	})?;
-	Ok(config)
-}
+#[derive(Debug, Parser)]
+struct Opt {
+	#[clap(flatten)]
+	config: config::ConfigOpt,
 
-#[derive(Debug, Deserialize)]
-pub struct Config {
-	pub telegram: tg::Config,
-	pub matrix: matrix::Config,
-	#[serde(default)]
-	pub sticker: AnimationFormat
+	#[clap(subcommand)]
+	command: Command
 }
 
-#[derive(Debug, Parser)]
-enum Opt {
+#[derive(Debug, Subcommand)]
+enum Command {
+	/// create the config file interactive
+	Init(init::Opt),
 	/// import Stickerpack from telegram
 	Import(import::Opt),
+	/// import Stickerpack from a local directory of images
+	ImportDir(import_dir::Opt),
+	/// import the stickers of a chat history exported by Telegram Desktop
+	ImportChatExport(import_chat_export::Opt),
+	/// import Stickerpack from an archive created by `export`
+	ImportArchive(import_archive::Opt),
+	/// create a Stickerpack from the stickers sent at a matrix room
+	CollectRoom(collect_room::Opt),
+	/// export a Stickerpack with all its files to an archive
+	Export(export::Opt),
+	/// create a new telegram sticker set from a Stickerpack
+	ExportTelegram(export_telegram::Opt),
+	/// convert a Stickerpack to `.wastickers` archives for WhatsApp
+	ExportWhatsapp(export_whatsapp::Opt),
+	/// upload the files of Stickerpacks to the configured homeserver and update their urls
+	Mirror(mirror::Opt),
+	/// run a telegram bot, which imports the packs of stickers and links sent to it
+	Bot(bot::Opt),
+	/// run a matrix bot, which adds the packs linked at rooms as room emotes
+	MatrixBot(matrix_bot::Opt),
+	/// update previously imported Stickerpacks to the current state at telegram
+	Update(update::Opt),
 	/// enable a custom sticker picker widget in a supported Matirx client
 	/// ⚠️_Warning: make sure you have send a sticker (since creating your matrix account) using the Element stickerpicker, before excuting this subcommand or the widget will not work.
 	SetWidget(set_widget::Opt),
@@ -63,7 +70,13 @@ enum Opt {
 	ShellCompletion(print_shell_completion::Opt),
 	/// create the `index.json` from the local stickerpacks for maunium/stickerpicker.
 	/// not need for msrd0/docker-stickerpicker (do not upload a `index.json` to the s3 bucket!)
-	CreateIndex(create_index::Opt)
+	CreateIndex(create_index::Opt),
+	/// create a complete self-hostable sticker picker widget from the local stickerpacks
+	BuildSite(build_site::Opt),
+	/// serve the sticker picker widget with the local stickerpacks
+	Serve(serve::Opt),
+	/// check the config, credentials and supported sticker formats
+	Doctor(doctor::Opt)
 }
 
 fn main() {
@@ -79,11 +92,28 @@ fn main() {
 		error!("Cannot create data dir {}: {}", data_dir.display(), err);
 		exit(1);
 	}
-	let result = match Opt::parse() {
-		Opt::Import(opt) => import::run(opt),
-		Opt::SetWidget(opt) => set_widget::run(opt),
-		Opt::ShellCompletion(opt) => print_shell_completion::run(opt),
-		Opt::CreateIndex(opt) => create_index::run(opt)
+	let opt = Opt::parse();
+	config::CONFIG_OPT.set(opt.config).unwrap();
+	let result = match opt.command {
+		Command::Init(opt) => init::run(opt),
+		Command::Import(opt) => import::run(opt),
+		Command::ImportDir(opt) => import_dir::run(opt),
+		Command::ImportChatExport(opt) => import_chat_export::run(opt),
+		Command::ImportArchive(opt) => import_archive::run(opt),
+		Command::CollectRoom(opt) => collect_room::run(opt),
+		Command::Export(opt) => export::run(opt),
+		Command::ExportTelegram(opt) => export_telegram::run(opt),
+		Command::ExportWhatsapp(opt) => export_whatsapp::run(opt),
+		Command::Mirror(opt) => mirror::run(opt),
+		Command::Bot(opt) => bot::run(opt),
+		Command::MatrixBot(opt) => matrix_bot::run(opt),
+		Command::Update(opt) => update::run(opt),
+		Command::SetWidget(opt) => set_widget::run(opt),
+		Command::ShellCompletion(opt) => print_shell_completion::run(opt),
+		Command::CreateIndex(opt) => create_index::run(opt),
+		Command::BuildSite(opt) => build_site::run(opt),
+		Command::Serve(opt) => serve::run(opt),
+		Command::Doctor(opt) => doctor::run(opt)
 	};
 	if let Err(error) = result {
 		error!("{error:?}");
@@ -0,0 +1,367 @@
+use anyhow::{bail, Context as _};
+use clap::ArgEnum;
+use mstickerlib::{
+	image::{sniff_format, Image},
+	matrix::{
+		self,
+		sticker_formats::{maunium, ponies},
+		Mxc
+	}
+};
+use serde_json::{Map, Value};
+use std::{
+	collections::{HashMap, HashSet},
+	ffi::OsString,
+	path::Path,
+	sync::Arc
+};
+use tokio::fs;
+
+/// prefix of the keys added to pack files by mstickereditor
+const OWN_KEY_PREFIX: &str = "dev.luckyturtle.mstickereditor.";
+
+/// format of a stickerpack file
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum Format {
+	Maunium,
+	Ponies
+}
+
+/// read a pack file and detect its format
+pub async fn read_pack(path: &Path) -> anyhow::Result<(Format, matrix::stickerpack::StickerPack)> {
+	let data = fs::read(path).await?;
+	parse_pack(serde_json::from_slice(&data)?)
+}
+
+/// detect the format of a pack and parse it
+pub fn parse_pack(value: serde_json::Value) -> anyhow::Result<(Format, matrix::stickerpack::StickerPack)> {
+	if value.get("stickers").is_some() {
+		let pack: maunium::StickerPack = serde_json::from_value(value)?;
+		Ok((Format::Maunium, pack.into()))
+	} else if value.get("images").is_some() {
+		let pack: ponies::StickerPack = serde_json::from_value(value)?;
+		Ok((Format::Ponies, pack.into()))
+	} else {
+		bail!("file is neither a maunium nor a ponies pack")
+	}
+}
+
+/// serialize a pack in the given format
+pub fn to_json(format: Format, pack: matrix::stickerpack::StickerPack) -> serde_json::Result<String> {
+	match format {
+		Format::Maunium => serde_json::to_string(&maunium::StickerPack::from(pack)),
+		Format::Ponies => serde_json::to_string(&ponies::StickerPack::from(pack))
+	}
+}
+
+/// all images, thumbnails and premium animations of the pack and its avatar; each url is only included once
+pub fn unique_images(pack: &matrix::stickerpack::StickerPack) -> Vec<&matrix::sticker::Image> {
+	let mut urls = HashSet::new();
+	pack.stickers
+		.iter()
+		.flat_map(|sticker| {
+			std::iter::once(&sticker.image)
+				.chain(sticker.thumbnail.as_ref())
+				.chain(sticker.premium_animation.as_ref())
+		})
+		.chain(pack.avatar.as_ref())
+		.filter(|image| urls.insert(image.url.url()))
+		.collect()
+}
+
+/// Create an [Image] from the file `data` of `image`, so it can be uploaded again.
+/// The format is detected by the content, since avatars set by other tools have no meta data.
+pub fn to_upload(image: &matrix::sticker::Image, data: Arc<Vec<u8>>) -> anyhow::Result<Image> {
+	let (_, media_id) = image.url.parts()?;
+	let (extension, width, height) = sniff_format(&data).unwrap_or_else(|| {
+		let extension = image.meta_data.mimetype.split('/').next_back().unwrap_or("bin");
+		(extension, image.meta_data.w, image.meta_data.h)
+	});
+	Ok(Image {
+		file_name: format!("{media_id}.{extension}"),
+		data,
+		width,
+		height
+	})
+}
+
+/// Replace the urls at the json of a pack file; `new_urls` maps the old url to the new one.
+/// In contrast to [replace_urls] all other values are kept, including fields unknown to mstickereditor.
+pub fn replace_json_urls(value: &mut serde_json::Value, new_urls: &HashMap<String, Mxc>) {
+	match value {
+		serde_json::Value::String(url) => {
+			if let Some(mxc) = new_urls.get(url.as_str()) {
+				*url = mxc.url().to_owned();
+			}
+		},
+		serde_json::Value::Array(values) => values.iter_mut().for_each(|value| replace_json_urls(value, new_urls)),
+		serde_json::Value::Object(map) => map.values_mut().for_each(|value| replace_json_urls(value, new_urls)),
+		_ => {}
+	}
+}
+
+/// Replace the keys of mstickereditor at `old` by the ones of `new`; all other keys are kept.
+fn refresh_own_keys(old: &mut Map<String, Value>, new: &Map<String, Value>) {
+	old.retain(|key, _| !key.starts_with(OWN_KEY_PREFIX) || new.contains_key(key));
+	for (key, value) in new.iter().filter(|(key, _)| key.starts_with(OWN_KEY_PREFIX)) {
+		old.insert(key.clone(), value.clone());
+	}
+}
+
+/// url of a sticker at the json of a pack file
+fn sticker_url(sticker: &Value) -> Option<&str> {
+	sticker.get("url").and_then(Value::as_str)
+}
+
+/// Write the updated `pack` into the json `old` of its pack file, keeping all values unknown to mstickereditor.
+///
+/// Stickers are matched by their url: old stickers keep their json and only get the keys of mstickereditor refreshed,
+/// new stickers are added and stickers, which are not part of `pack`, are removed.
+pub fn update_json(old: &mut Value, format: Format, pack: matrix::stickerpack::StickerPack) -> anyhow::Result<()> {
+	let new = match format {
+		Format::Maunium => serde_json::to_value(maunium::StickerPack::from(pack))?,
+		Format::Ponies => serde_json::to_value(ponies::StickerPack::from(pack))?
+	};
+	let (Value::Object(old), Value::Object(new)) = (old, new) else {
+		bail!("stickerpack is no json object");
+	};
+	refresh_own_keys(old, &new);
+	match format {
+		Format::Maunium => {
+			let mut old_stickers = match old.remove("stickers") {
+				Some(Value::Array(stickers)) => stickers,
+				_ => Vec::new()
+			};
+			let mut stickers = Vec::new();
+			for sticker in new.get("stickers").and_then(Value::as_array).into_iter().flatten() {
+				let position = old_stickers
+					.iter()
+					.position(|old| sticker_url(old).is_some() && sticker_url(old) == sticker_url(sticker));
+				match (position.map(|i| old_stickers.remove(i)), sticker) {
+					(Some(Value::Object(mut old)), Value::Object(new)) => {
+						refresh_own_keys(&mut old, new);
+						stickers.push(Value::Object(old));
+					},
+					_ => stickers.push(sticker.clone())
+				}
+			}
+			old.insert("stickers".to_owned(), Value::Array(stickers));
+		},
+		Format::Ponies => {
+			// the avatar is only changed if its url has changed, to keep avatars set by other tools as they are
+			if let (Some(Value::Object(old_info)), Some(Value::Object(new_info))) = (old.get_mut("pack"), new.get("pack")) {
+				if old_info.get("avatar_url") != new_info.get("avatar_url") {
+					old_info.insert(
+						"avatar_url".to_owned(),
+						new_info.get("avatar_url").cloned().unwrap_or(Value::Null)
+					);
+					refresh_own_keys(old_info, new_info);
+				}
+			}
+			let mut old_images = match old.remove("images") {
+				Some(Value::Object(images)) => images,
+				_ => Map::new()
+			};
+			let mut images = Map::new();
+			for (key, image) in new.get("images").and_then(Value::as_object).into_iter().flatten() {
+				let old_key = old_images
+					.iter()
+					.find(|(_, old)| sticker_url(old).is_some() && sticker_url(old) == sticker_url(image))
+					.map(|(key, _)| key.clone());
+				match (old_key.and_then(|key| old_images.remove_entry(&key)), image) {
+					(Some((key, Value::Object(mut old))), Value::Object(new)) => {
+						refresh_own_keys(&mut old, new);
+						images.insert(key, Value::Object(old));
+					},
+					_ => {
+						// keys of new stickers may be used by old stickers already
+						let mut key = key.clone();
+						while images.contains_key(&key) || old_images.contains_key(&key) {
+							key.push('_');
+						}
+						images.insert(key, image.clone());
+					}
+				}
+			}
+			old.insert("images".to_owned(), Value::Object(images));
+		}
+	}
+	Ok(())
+}
+
+/// write a file by renaming a temporary file, so the old file is kept if writing fails
+pub async fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+	let mut tmp_name = OsString::from(".");
+	tmp_name.push(path.file_name().context("path has no file name")?);
+	tmp_name.push(".tmp");
+	let tmp_path = path.with_file_name(tmp_name);
+	fs::write(&tmp_path, data)
+		.await
+		.with_context(|| format!("failed to write {tmp_path:?}"))?;
+	fs::rename(&tmp_path, path)
+		.await
+		.with_context(|| format!("failed to rename {tmp_path:?} to {path:?}"))?;
+	Ok(())
+}
+
+/// replace the urls of the pack; `new_urls` maps the old url to the new one
+pub fn replace_urls(pack: &mut matrix::stickerpack::StickerPack, new_urls: &HashMap<String, Mxc>) {
+	if let Some(avatar) = &mut pack.avatar {
+		if let Some(mxc) = new_urls.get(avatar.url.url()) {
+			avatar.url = mxc.clone();
+		}
+	}
+	for sticker in &mut pack.stickers {
+		let images = std::iter::once(&mut sticker.image)
+			.chain(sticker.thumbnail.as_mut())
+			.chain(sticker.premium_animation.as_mut());
+		for image in images {
+			if let Some(mxc) = new_urls.get(image.url.url()) {
+				image.url = mxc.clone();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_pack, replace_json_urls, to_upload, update_json, Format};
+	use mstickerlib::matrix::{self, sticker_formats::ponies, Mxc};
+	use serde_json::json;
+	use std::{collections::HashMap, sync::Arc};
+
+	#[test]
+	fn json_urls() {
+		let mut pack = json!({
+			"title": "Cats",
+			"id": "tg_cats",
+			"net.maunium.telegram.pack": {"short_name": "cats", "hash": "abc"},
+			"stickers": [{
+				"body": "cat",
+				"url": "mxc://old.org/cat",
+				"info": {"w": 256, "h": 256, "mimetype": "image/webp", "thumbnail_url": "mxc://old.org/cat"},
+				"msgtype": "m.sticker",
+				"id": "12345",
+				"net.maunium.telegram.sticker": {"id": "12345", "emoticons": ["😺"], "pack": {"short_name": "cats"}}
+			}, {
+				"body": "dog",
+				"url": "mxc://old.org/dog",
+				"info": {"w": 256, "h": 256, "mimetype": "image/webp"}
+			}]
+		});
+		let mut expected = pack.clone();
+		expected["stickers"][0]["url"] = json!("mxc://new.org/cat");
+		expected["stickers"][0]["info"]["thumbnail_url"] = json!("mxc://new.org/cat");
+		let new_urls = HashMap::from([("mxc://old.org/cat".to_owned(), Mxc::from("mxc://new.org/cat".to_owned()))]);
+		replace_json_urls(&mut pack, &new_urls);
+		assert_eq!(pack, expected);
+	}
+
+	#[test]
+	fn upload_foreign_avatar() {
+		let pack: ponies::StickerPack = serde_json::from_value(json!({
+			"images": {},
+			"pack": { "display_name": "Cats", "avatar_url": "mxc://example.org/avatar" }
+		}))
+		.unwrap();
+		let pack = matrix::stickerpack::StickerPack::from(pack);
+		let avatar = pack.avatar.unwrap();
+		assert_eq!(avatar.meta_data.mimetype, "application/octet-stream");
+		let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+		png.extend_from_slice(&[0, 0, 0, 96, 0, 0, 0, 64]);
+		let upload = to_upload(&avatar, Arc::new(png)).unwrap();
+		assert_eq!(upload.file_name, "avatar.png");
+		assert_eq!(upload.mime_type().unwrap(), "image/png");
+		assert_eq!((upload.width, upload.height), (96, 64));
+	}
+
+	/// matrix sticker with the url `mxc://example.org/<name>`
+	fn new_sticker(name: &str) -> matrix::sticker::Sticker {
+		serde_json::from_value(json!({
+			"body": name,
+			"image": {
+				"url": format!("mxc://example.org/{name}"),
+				"meta_data": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" }
+			},
+			"thumbnail": null,
+			"emoticon": null,
+			"emoji": ["🐶"],
+			"tg_sticker": { "pack_name": "cats", "bot_api_id": null, "bot_api_unique_id": "u3", "client_api_id": null, "emoji": ["🐶"], "index": 1 }
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn update_maunium_json() {
+		let mut json = json!({
+			"title": "Cats",
+			"id": "tg_name_cats",
+			"net.maunium.telegram.pack": { "short_name": "cats", "hash": "abc" },
+			"custom": "kept",
+			"stickers": [{
+				"body": "cat",
+				"url": "mxc://example.org/cat",
+				"info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp", "thumbnail_url": "mxc://example.org/cat", "thumbnail_info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" } },
+				"msgtype": "m.sticker",
+				"id": "12345",
+				"net.maunium.telegram.sticker": { "id": "12345", "emoticons": ["😺"], "pack": { "id": "1", "short_name": "cats" } },
+				"dev.luckyturtle.mstickereditor.tg_sticker": { "pack_name": "cats", "bot_api_id": "f1", "bot_api_unique_id": "u1", "client_api_id": null, "emoji": ["😺"], "index": 0 },
+				"custom": "kept"
+			}, {
+				"body": "removed",
+				"url": "mxc://example.org/removed",
+				"info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp", "thumbnail_url": "mxc://example.org/removed", "thumbnail_info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" } },
+				"msgtype": "m.sticker",
+				"id": "removed",
+				"net.maunium.telegram.sticker": null
+			}]
+		});
+		let (format, mut pack) = parse_pack(json.clone()).unwrap();
+		assert!(matches!(format, Format::Maunium));
+		pack.stickers.remove(1);
+		pack.stickers[0].tg_sticker.as_mut().unwrap().index = Some(2);
+		pack.stickers.push(new_sticker("dog"));
+		update_json(&mut json, format, pack).unwrap();
+
+		assert_eq!(json["custom"], "kept");
+		assert_eq!(json["net.maunium.telegram.pack"]["hash"], "abc");
+		let stickers = json["stickers"].as_array().unwrap();
+		assert_eq!(stickers.len(), 2);
+		assert_eq!(stickers[0]["custom"], "kept");
+		assert_eq!(stickers[0]["id"], "12345");
+		assert_eq!(stickers[0]["net.maunium.telegram.sticker"]["emoticons"], json!(["😺"]));
+		assert_eq!(stickers[0]["dev.luckyturtle.mstickereditor.tg_sticker"]["index"], 2);
+		assert_eq!(stickers[1]["url"], "mxc://example.org/dog");
+		assert_eq!(
+			stickers[1]["dev.luckyturtle.mstickereditor.tg_sticker"]["bot_api_unique_id"],
+			"u3"
+		);
+	}
+
+	#[test]
+	fn update_ponies_json() {
+		let mut json = json!({
+			"pack": { "display_name": "Cats", "avatar_url": "mxc://example.org/avatar", "custom": "kept" },
+			"images": {
+				"0001": { "body": "cat", "url": "mxc://example.org/cat", "info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" }, "usage": ["sticker"], "custom": "kept" },
+				"0000": { "body": "removed", "url": "mxc://example.org/removed", "info": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" }, "usage": ["sticker"] }
+			},
+			"dev.luckyturtle.mstickereditor.tg_pack": { "name": "cats", "title": "Cats" }
+		});
+		let (format, mut pack) = parse_pack(json.clone()).unwrap();
+		assert!(matches!(format, Format::Ponies));
+		pack.stickers.retain(|sticker| sticker.body == "cat");
+		pack.stickers.push(new_sticker("dog"));
+		update_json(&mut json, format, pack).unwrap();
+
+		assert_eq!(json["pack"]["custom"], "kept");
+		// the avatar has no meta data, which must not be added since it is unchanged
+		assert!(json["pack"].get("dev.luckyturtle.mstickereditor.avatar_info").is_none());
+		let images = json["images"].as_object().unwrap();
+		let keys: Vec<&str> = images.keys().map(String::as_str).collect();
+		// the new sticker would get the key `0001`, which is already used
+		assert_eq!(keys, ["0001", "0001_"]);
+		assert_eq!(images["0001"]["custom"], "kept");
+		assert_eq!(images["0001_"]["url"], "mxc://example.org/dog");
+	}
+}
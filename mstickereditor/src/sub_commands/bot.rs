@@ -0,0 +1,185 @@
+use super::import::{import_pack, ImportOpt};
+use crate::{config::Config, load_config_file};
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use log::{error, info, warn};
+use mstickerlib::{
+	database::DynDatabase,
+	tg::{self, Message, PackRef}
+};
+use std::{path::Path, time::Duration};
+use tokio::{fs, time::sleep};
+
+/// seconds to wait for new messages at each request
+const POLL_TIMEOUT: u64 = 60;
+/// delay after a failed request, before trying again
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+const HELP: &str = "Send me a sticker or a link like https://t.me/addstickers/<name> and I will import its pack.";
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Telegram user id, which is allowed to use the bot. Can be used multiple times.
+	/// Other users get their user id as answer.
+	#[clap(long = "allow", value_name = "USER_ID", required = true)]
+	allowed_users: Vec<u64>,
+
+	/// Only answer with a message and do not send the stickerpack file back
+	#[clap(long)]
+	no_file: bool,
+
+	#[clap(flatten)]
+	import: ImportOpt
+}
+
+/// Get the names of the packs referenced by a message, either by the set of a sticker or by links at the text.
+/// A single word is also accepted as pack name.
+pub(crate) fn pack_names(set_name: Option<&str>, text: Option<&str>) -> Vec<String> {
+	let text = text.unwrap_or_default();
+	let mut names: Vec<String> = set_name.into_iter().map(ToOwned::to_owned).collect();
+	let links = match text.parse::<PackRef>() {
+		Ok(pack) => vec![pack.name().to_owned()],
+		Err(_) => PackRef::find_links(text)
+			.into_iter()
+			.map(|pack| pack.name().to_owned())
+			.collect()
+	};
+	for name in links {
+		if !names.contains(&name) {
+			names.push(name);
+		}
+	}
+	names
+}
+
+/// import the packs of a message and answer with the result
+async fn handle_message(
+	message: &Message,
+	opt: &Opt,
+	config: &Config,
+	database: Option<&dyn DynDatabase>,
+	journal_dir: &Path
+) -> anyhow::Result<()> {
+	let chat = *message.chat().id();
+	let reply_to = Some(*message.message_id());
+	let reply = |text: String| async move {
+		tg::send_message(&config.telegram, chat, &text, reply_to)
+			.await
+			.context("failed to answer message")
+	};
+
+	let user = message.from().as_ref().map(|user| *user.id());
+	let Some(user) = user.filter(|user| opt.allowed_users.contains(user)) else {
+		warn!("ignore message of not allowed user {user:?}");
+		let text = match user {
+			Some(user) => format!("You are not allowed to use this bot. Your user id is {user}."),
+			None => "You are not allowed to use this bot.".to_owned()
+		};
+		return reply(text).await;
+	};
+
+	let sticker = message.sticker().as_ref();
+	let packs = pack_names(
+		sticker.and_then(|sticker| sticker.set_name().as_deref()),
+		message.text().as_deref()
+	);
+	if packs.is_empty() {
+		let text = match sticker {
+			Some(_) => "This sticker does not belong to a pack.".to_owned(),
+			None => HELP.to_owned()
+		};
+		return reply(text).await;
+	}
+
+	for pack in packs {
+		info!("user {user} requested pack {pack}");
+		reply(format!("Importing {pack} …")).await?;
+		let imported = match import_pack(&pack, &opt.import, config, database, journal_dir).await {
+			Ok(imported) => imported,
+			Err(err) => {
+				error!("{err:?}");
+				reply(format!("Failed to import {pack}: {err:#}")).await?;
+				continue;
+			}
+		};
+		let Some(path) = imported.path else {
+			error!("Sticker pack {} is empty", imported.name);
+			reply(format!("Failed to import {pack}: no sticker could be imported")).await?;
+			continue;
+		};
+		let mut text = format!("Imported {} stickers of {:?}", imported.stickers, imported.title);
+		if !imported.complete {
+			text.push_str(", but the pack is not complete. See the log for details.");
+		}
+		if opt.no_file {
+			reply(text).await?;
+			continue;
+		}
+		let data = fs::read(&path)
+			.await
+			.with_context(|| format!("failed to read stickerpack {path:?}"))?;
+		let file_name = path
+			.file_name()
+			.ok_or_else(|| anyhow!("invalid stickerpack path {path:?}"))?
+			.to_string_lossy();
+		tg::send_document(&config.telegram, chat, &file_name, data, Some(&text))
+			.await
+			.context("failed to send stickerpack")?;
+	}
+	Ok(())
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let bot = tg::get_me(&config.telegram).await.context("Error connecting to Telegram")?;
+	config.check_matrix(opt.import.dryrun).await?;
+	std::fs::create_dir_all(&opt.import.output_dir)
+		.with_context(|| format!("failed to create dir {:?}", opt.import.output_dir))?;
+	let database = config.database.open(&config.data_dir).await?;
+	let journal_dir = config.data_dir.join("journal");
+	std::fs::create_dir_all(&journal_dir).with_context(|| format!("failed to create dir {journal_dir:?}"))?;
+
+	info!(
+		"bot @{} is waiting for messages",
+		bot.username().as_deref().unwrap_or(bot.first_name())
+	);
+	let mut offset = None;
+	loop {
+		let updates = match tg::get_updates(&config.telegram, offset, POLL_TIMEOUT).await {
+			Ok(updates) => updates,
+			Err(err) => {
+				warn!("{:?}", anyhow::Error::from(err).context("failed to receive messages"));
+				sleep(RETRY_DELAY).await;
+				continue;
+			}
+		};
+		for update in updates {
+			offset = Some(update.update_id() + 1);
+			let Some(message) = update.message() else {
+				continue;
+			};
+			if let Err(err) = handle_message(message, &opt, &config, database.as_deref(), &journal_dir).await {
+				error!("{err:?}");
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::pack_names;
+
+	#[test]
+	fn message_packs() {
+		assert_eq!(pack_names(Some("Cats"), None), ["Cats"]);
+		assert_eq!(pack_names(None, Some("Cats")), ["Cats"]);
+		assert_eq!(
+			pack_names(None, Some("look at https://t.me/addstickers/Cats and t.me/addemoji/Dogs")),
+			["Cats", "Dogs"]
+		);
+		assert_eq!(pack_names(Some("Cats"), Some("https://t.me/addstickers/Cats")), ["Cats"]);
+		assert!(pack_names(None, Some("hello there")).is_empty());
+		assert!(pack_names(None, Some("/start")).is_empty());
+	}
+}
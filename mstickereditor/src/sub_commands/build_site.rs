@@ -0,0 +1,91 @@
+use super::create_index::{create_index, find_packs};
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::info;
+use std::{fs, path::PathBuf};
+
+/// static files of the sticker picker widget
+pub(crate) const PICKER_ASSETS: &[(&str, &str)] = &[
+	("index.html", include_str!("../../assets/picker/index.html")),
+	("picker.js", include_str!("../../assets/picker/picker.js")),
+	("style.css", include_str!("../../assets/picker/style.css"))
+];
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Directory to create the sticker picker at
+	#[clap(value_name = "DIR")]
+	output_dir: PathBuf,
+
+	/// Directory containing the stickerpacks, which should be included
+	#[clap(short = 'd', long, value_name = "DIR", default_value = ".")]
+	packs_dir: PathBuf,
+
+	/// Matrix homeserver, with does render the preview thumbs
+	#[clap(short = 's', long)]
+	homeserver: Option<String>,
+
+	/// pretty human readable index.json
+	#[clap(short, long)]
+	pretty: bool
+}
+
+pub fn run(opt: Opt) -> anyhow::Result<()> {
+	let packs = find_packs(&opt.packs_dir)?;
+	let packs_output_dir = opt.output_dir.join("packs");
+	if packs_output_dir.exists() && packs_output_dir.canonicalize()? == opt.packs_dir.canonicalize()? {
+		bail!(
+			"{:?} can not be used as packs dir, since the packs are copied to the output dir",
+			opt.packs_dir
+		);
+	}
+	fs::create_dir_all(&packs_output_dir).with_context(|| format!("Error: could not create dir {packs_output_dir:?}"))?;
+	for (file_name, content) in PICKER_ASSETS {
+		let path = opt.output_dir.join(file_name);
+		fs::write(&path, content).with_context(|| format!("Error: could not save {path:?}"))?;
+	}
+	for pack in packs {
+		let path = packs_output_dir.join(&pack);
+		fs::copy(opt.packs_dir.join(&pack), &path).with_context(|| format!("Error: could not save {path:?}"))?;
+	}
+	// the order of the existing index and packs added to the output dir by the user are kept
+	create_index(&packs_output_dir, opt.homeserver, opt.pretty)?;
+	info!("sticker picker was created at {:?}", opt.output_dir);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{run, Opt};
+	use crate::sub_commands::create_index::Index;
+	use std::fs;
+
+	#[test]
+	fn keep_index_order() {
+		let packs_dir = tempfile::tempdir().unwrap();
+		let output_dir = tempfile::tempdir().unwrap();
+		let pack = |title: &str| format!(r#"{{"title": "{title}", "id": "{title}", "stickers": []}}"#);
+		for name in ["a", "b", "c"] {
+			fs::write(packs_dir.path().join(format!("{name}.json")), pack(name)).unwrap();
+		}
+		let packs_output_dir = output_dir.path().join("packs");
+		fs::create_dir(&packs_output_dir).unwrap();
+		fs::write(packs_output_dir.join("extra.json"), pack("extra")).unwrap();
+		fs::write(
+			packs_output_dir.join("index.json"),
+			r#"{"packs": ["b.json", "extra.json"], "homeserver_url": "https://example.org"}"#
+		)
+		.unwrap();
+
+		run(Opt {
+			output_dir: output_dir.path().to_owned(),
+			packs_dir: packs_dir.path().to_owned(),
+			homeserver: Some("https://example.org".to_owned()),
+			pretty: false
+		})
+		.unwrap();
+		let index: Index = serde_json::from_slice(&fs::read(packs_output_dir.join("index.json")).unwrap()).unwrap();
+		assert_eq!(index.packs, ["b.json", "extra.json", "a.json", "c.json"]);
+		assert!(output_dir.path().join("picker.js").exists());
+	}
+}
@@ -0,0 +1,98 @@
+use super::import::file_name;
+use crate::{
+	load_config_file,
+	pack_file::{to_json, Format}
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::info;
+use mstickerlib::matrix::{self, room, stickerpack::StickerPack};
+use std::path::PathBuf;
+use tokio::fs;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Id of the room, like `!abc:example.org`
+	room: String,
+
+	/// Title of the pack [default: name of the room]
+	#[clap(long)]
+	title: Option<String>,
+
+	/// Read at most this number of stickers sent at the room, starting with the newest one
+	#[clap(long, value_name = "N")]
+	limit: Option<usize>,
+
+	/// Publish the pack as room emotes of the room, instead of saving it.
+	/// A previous pack with the same state key is replaced.
+	#[clap(long, value_name = "STATE_KEY")]
+	publish: Option<String>,
+
+	/// Format of the created stickerpack
+	#[clap(long, arg_enum, default_value = "maunium")]
+	format: Format,
+
+	/// Directory to save the stickerpack to
+	#[clap(short, long, value_name = "DIR", default_value = ".")]
+	output_dir: PathBuf,
+
+	/// File name of the stickerpack.
+	/// `{name}`, `{title}` and `{id}` are replaced by the values of the pack; `{name}` is the title of the pack.
+	#[clap(long, value_name = "TEMPLATE", default_value = "{name}.json")]
+	file_name: String
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	matrix::whoami(&config.matrix)
+		.await
+		.context("Error connecting to Matrix homeserver")?;
+
+	// encrypted events are not returned by the sticker filter, so the pack would just be empty
+	if room::is_encrypted(&config.matrix, &opt.room)
+		.await
+		.with_context(|| format!("failed to check the encryption of room {}", opt.room))?
+	{
+		bail!(
+			"room {} is encrypted; encrypted stickers can not be read, since mstickereditor does not support encryption",
+			opt.room
+		);
+	}
+	let stickers = room::collect_stickers(&config.matrix, &opt.room, opt.limit)
+		.await
+		.with_context(|| format!("failed to read the history of room {}", opt.room))?;
+	if stickers.is_empty() {
+		bail!("no stickers were sent at room {}", opt.room);
+	}
+	let title = match opt.title {
+		Some(title) => title,
+		None => room::room_name(&config.matrix, &opt.room)
+			.await
+			.with_context(|| format!("failed to get the name of room {}", opt.room))?
+			.unwrap_or_else(|| opt.room.clone())
+	};
+	info!("found {} different stickers at room {}", stickers.len(), opt.room);
+	let pack = StickerPack {
+		id: format!("room_{}", opt.room.trim_start_matches('!').replace(':', "_")),
+		title,
+		tg_pack: None,
+		avatar: None,
+		stickers
+	};
+
+	if let Some(state_key) = opt.publish {
+		info!("publish stickerpack as room emotes {state_key:?}");
+		room::set_room_emotes(&config.matrix, &opt.room, &state_key, pack)
+			.await
+			.context("failed to set room emotes")?;
+		return Ok(());
+	}
+	std::fs::create_dir_all(&opt.output_dir).with_context(|| format!("failed to create dir {:?}", opt.output_dir))?;
+	let path = opt
+		.output_dir
+		.join(file_name(&opt.file_name, &pack.title, &pack.title, &pack.id));
+	info!("save stickerpack to {:?}", path);
+	fs::write(path, to_json(opt.format, pack)?).await?;
+	Ok(())
+}
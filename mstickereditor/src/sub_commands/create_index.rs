@@ -1,8 +1,13 @@
 use crate::load_config_file;
 use anyhow::{bail, Context};
 use clap::Parser;
+use log::warn;
+use mstickerlib::matrix::sticker_formats::maunium;
 use serde::{Deserialize, Serialize};
-use std::fs::read_dir;
+use std::{
+	fs,
+	path::{Path, PathBuf}
+};
 
 #[derive(Debug, Parser)]
 pub struct Opt {
@@ -12,41 +17,111 @@ pub struct Opt {
 
 	/// Matrix homeserver, with does render the preview thumbs
 	#[clap(short = 's', long)]
-	homeserver: Option<String>
+	homeserver: Option<String>,
+
+	/// Directory containing the stickerpacks. The `index.json` is also saved there.
+	#[clap(short = 'd', long, value_name = "DIR", default_value = ".")]
+	packs_dir: PathBuf
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-struct Index {
-	packs: Vec<String>,
+pub(crate) struct Index {
+	pub(crate) packs: Vec<String>,
 	homeserver_url: String
 }
 
-pub fn run(opt: Opt) -> anyhow::Result<()> {
+/// Keep the order of the `old` packs, which still exist, and append the new packs.
+fn merge_packs(old: Vec<String>, mut found: Vec<String>) -> Vec<String> {
+	let mut packs: Vec<String> = old.into_iter().filter(|pack| found.contains(pack)).collect();
+	found.sort();
+	for pack in found {
+		if !packs.contains(&pack) {
+			packs.push(pack);
+		}
+	}
+	packs
+}
+
+/// file names of all maunium stickerpacks at `dir`
+pub(crate) fn find_packs(dir: &Path) -> anyhow::Result<Vec<String>> {
 	let mut packs: Vec<String> = Vec::new();
-	let paths = read_dir("./")?;
+	let paths = fs::read_dir(dir).with_context(|| format!("Error: could not read {dir:?}"))?;
 	for path in paths {
 		let path = path?.path();
-		let file = path.file_name();
-		if let Some(file) = file {
-			let file = file.to_str().unwrap();
-			if file.ends_with(".json") && !file.ends_with("index.json") {
-				println!("add pack {}", &file[..file.len() - 5]);
-				packs.push(file.into())
-			}
+		let Some(file) = path.file_name().and_then(|file| file.to_str()) else {
+			continue;
+		};
+		if !file.ends_with(".json") || file == "index.json" {
+			continue;
+		}
+		// other json files can also be located at the directory
+		let pack = fs::read(&path)
+			.map_err(anyhow::Error::from)
+			.and_then(|data| Ok(serde_json::from_slice::<maunium::StickerPack>(&data)?));
+		match pack {
+			Ok(_) => packs.push(file.to_owned()),
+			Err(err) => warn!("skip {file}, since it is not a maunium stickerpack: {err}")
 		}
 	}
 	if packs.is_empty() {
-		bail!("Error: no stickerpacks found at working directory")
+		bail!("Error: no stickerpacks found at {dir:?}")
 	}
-	let homeserver_url = match opt.homeserver {
+	Ok(packs)
+}
+
+/// Index of the stickerpacks at `packs_dir`.
+/// The order of an existing `index.json` is kept.
+pub(crate) fn build_index(packs_dir: &Path, homeserver: Option<String>) -> anyhow::Result<Index> {
+	let mut packs = find_packs(packs_dir)?;
+	let index_path = packs_dir.join("index.json");
+	if index_path.exists() {
+		let old_index: Index = serde_json::from_slice(&fs::read(&index_path)?)
+			.with_context(|| format!("Error: could not read existing {index_path:?}"))?;
+		for pack in old_index.packs.iter().filter(|pack| !packs.contains(pack)) {
+			println!("remove pack {}", pack.trim_end_matches(".json"));
+		}
+		for pack in packs.iter().filter(|pack| !old_index.packs.contains(pack)) {
+			println!("add pack {}", pack.trim_end_matches(".json"));
+		}
+		packs = merge_packs(old_index.packs, packs);
+	} else {
+		packs.sort();
+		for pack in &packs {
+			println!("add pack {}", pack.trim_end_matches(".json"));
+		}
+	}
+
+	let homeserver_url = match homeserver {
 		Some(value) => value,
 		None => load_config_file()?.matrix.homeserver_url
 	};
-	let index = Index { packs, homeserver_url };
-	let string = match opt.pretty {
+	Ok(Index { packs, homeserver_url })
+}
+
+/// create or update the `index.json` of the stickerpacks at `packs_dir`
+pub(crate) fn create_index(packs_dir: &Path, homeserver: Option<String>, pretty: bool) -> anyhow::Result<()> {
+	let index = build_index(packs_dir, homeserver)?;
+	let string = match pretty {
 		true => serde_json::to_string_pretty(&index).unwrap(),
 		false => serde_json::to_string(&index).unwrap()
 	};
-	std::fs::write("index.json", string).context("Error: could not save `index.json`")?;
+	let index_path = packs_dir.join("index.json");
+	fs::write(&index_path, string).with_context(|| format!("Error: could not save {index_path:?}"))?;
 	Ok(())
 }
+
+pub fn run(opt: Opt) -> anyhow::Result<()> {
+	create_index(&opt.packs_dir, opt.homeserver, opt.pretty)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::merge_packs;
+
+	#[test]
+	fn merge_keeps_order() {
+		let old = ["b", "gone", "a"].map(String::from).to_vec();
+		let found = ["d", "a", "c", "b"].map(String::from).to_vec();
+		assert_eq!(merge_packs(old, found), ["b", "a", "c", "d"]);
+	}
+}
@@ -0,0 +1,186 @@
+use crate::{
+	config::{config_file_path, Config},
+	load_config_file
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+#[cfg(any(feature = "ffmpeg", feature = "lottie"))]
+use mstickerlib::image::Image;
+use mstickerlib::{image::AnimationFormat, matrix, tg};
+use std::fmt::Display;
+#[cfg(any(feature = "ffmpeg", feature = "lottie"))]
+use std::sync::Arc;
+
+/// tiny animated sticker (gzip compressed lottie animation)
+#[cfg(feature = "lottie")]
+const SAMPLE_TGS: &[u8] = include_bytes!("../../assets/doctor/sample.tgs");
+/// tiny video sticker (webm with two frames)
+#[cfg(feature = "ffmpeg")]
+const SAMPLE_WEBM: &[u8] = include_bytes!("../../assets/doctor/sample.webm");
+
+#[derive(Debug, Parser)]
+pub struct Opt {}
+
+enum Status {
+	Pass,
+	Fail,
+	Skip
+}
+
+impl Display for Status {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Pass => write!(f, "pass"),
+			Self::Fail => write!(f, "FAIL"),
+			Self::Skip => write!(f, "skip")
+		}
+	}
+}
+
+#[derive(Default)]
+struct Report {
+	failed: bool
+}
+
+impl Report {
+	fn row(&mut self, check: &str, status: Status, detail: impl Display) {
+		if matches!(status, Status::Fail) {
+			self.failed = true;
+		}
+		println!("{status:<4}  {check:<24}  {detail}");
+	}
+
+	fn result<T: Display>(&mut self, check: &str, result: anyhow::Result<T>) {
+		match result {
+			Ok(detail) => self.row(check, Status::Pass, detail),
+			// print the whole error chain in one line
+			Err(err) => self.row(check, Status::Fail, format!("{err:#}"))
+		}
+	}
+
+	fn skip(&mut self, check: &str, reason: &str) {
+		self.row(check, Status::Skip, reason);
+	}
+}
+
+fn format_size(size: u64) -> String {
+	format!("{:.1} MiB", size as f64 / (1024.0 * 1024.0))
+}
+
+async fn check_config(report: &mut Report, config: &Config) {
+	report.result(
+		"telegram bot",
+		tg::get_me(&config.telegram)
+			.await
+			.map(|bot| format!("@{}", bot.username().as_deref().unwrap_or(bot.first_name())))
+			.context("invalid bot key")
+	);
+	match matrix::whoami(&config.matrix).await {
+		Ok(whoami) => {
+			if whoami.user_id() == &config.matrix.user {
+				report.row("matrix account", Status::Pass, whoami.user_id());
+			} else {
+				report.row(
+					"matrix account",
+					Status::Fail,
+					format!("access token belongs to {}, not to {}", whoami.user_id(), config.matrix.user)
+				);
+			}
+			report.result(
+				"matrix upload limit",
+				matrix::media_config(&config.matrix)
+					.await
+					.map(|media_config| {
+						media_config
+							.upload_size()
+							.map(format_size)
+							.unwrap_or_else(|| "not reported by homeserver".to_owned())
+					})
+					.map_err(anyhow::Error::from)
+			);
+		},
+		Err(err) => {
+			report.result::<String>("matrix account", Err(anyhow::Error::from(err)));
+			report.skip("matrix upload limit", "no valid matrix account");
+		}
+	}
+	// do not wait for other processes using the database
+	report.result("database", match config.database.open_nonblocking(&config.data_dir).await {
+		Ok(Some(_)) => Ok("readable"),
+		Ok(None) => Ok("disabled at config"),
+		Err(err) => Err(err)
+	});
+}
+
+#[cfg(feature = "lottie")]
+async fn convert_tgs(animation_format: AnimationFormat) -> anyhow::Result<String> {
+	let image = Image {
+		file_name: "sample.tgs".to_owned(),
+		data: Arc::new(SAMPLE_TGS.to_vec()),
+		width: 64,
+		height: 64
+	};
+	let image = image.unpack_tgs().await?.convert_lottie(animation_format).await?;
+	Ok(format!("{} ({} bytes)", image.file_name, image.data.len()))
+}
+
+#[cfg(feature = "ffmpeg")]
+async fn convert_webm() -> anyhow::Result<String> {
+	let image = Image {
+		file_name: "sample.webm".to_owned(),
+		data: Arc::new(SAMPLE_WEBM.to_vec()),
+		width: 32,
+		height: 32
+	};
+	let image = image.convert_webm2webp().await?;
+	Ok(format!("{} ({} bytes)", image.file_name, image.data.len()))
+}
+
+#[tokio::main]
+pub async fn run(_opt: Opt) -> anyhow::Result<()> {
+	let mut report = Report::default();
+	let config = load_config_file();
+	let animation_format = match &config {
+		Ok(config) => config.sticker,
+		Err(_) => AnimationFormat::default()
+	};
+	match config {
+		Ok(config) => {
+			report.row("config file", Status::Pass, config_file_path().display());
+			check_config(&mut report, &config).await;
+		},
+		Err(err) => {
+			report.result::<String>("config file", Err(err));
+			for check in ["telegram bot", "matrix account", "matrix upload limit", "database"] {
+				report.skip(check, "no valid config file");
+			}
+		}
+	}
+
+	#[cfg(feature = "lottie")]
+	{
+		report.row("feature lottie", Status::Pass, "enabled");
+		report.result("convert animated sticker", convert_tgs(animation_format).await);
+	}
+	#[cfg(not(feature = "lottie"))]
+	{
+		let _ = animation_format; //fix unused warning
+		report.skip("feature lottie", "disabled at compile time");
+		report.skip("convert animated sticker", "feature lottie is disabled");
+	}
+	#[cfg(feature = "ffmpeg")]
+	{
+		report.row("feature ffmpeg", Status::Pass, "enabled");
+		report.result("convert video sticker", convert_webm().await);
+	}
+	#[cfg(not(feature = "ffmpeg"))]
+	{
+		report.skip("feature ffmpeg", "disabled at compile time");
+		report.skip("convert video sticker", "feature ffmpeg is disabled");
+	}
+
+	if report.failed {
+		bail!("some checks have failed");
+	}
+	Ok(())
+}
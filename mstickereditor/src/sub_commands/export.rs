@@ -0,0 +1,116 @@
+use crate::{
+	load_config_file,
+	pack_file::{read_pack, unique_images}
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+use flate2::{write::GzEncoder, Compression};
+use futures_util::stream::{self, StreamExt as _, TryStreamExt as _};
+use log::info;
+use mstickerlib::matrix::{self, Mxc};
+use serde::{Deserialize, Serialize};
+use std::{
+	fs::File,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+/// file name of the manifest inside the archive
+pub(crate) const MANIFEST: &str = "manifest.json";
+/// version of the archive format
+pub(crate) const ARCHIVE_VERSION: u32 = 1;
+
+/// Manifest of an archive. The stickerpack does include titles, emoji, emoticons and the telegram information.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ArchiveManifest {
+	pub(crate) version: u32,
+	pub(crate) pack: matrix::stickerpack::StickerPack
+}
+
+/// path of the file of `mxc` inside the archive
+pub(crate) fn media_path(mxc: &Mxc) -> anyhow::Result<String> {
+	let (server_name, media_id) = mxc.parts()?;
+	// server names and media ids must not point outside of the media dir, e.g. by using `..`
+	let is_safe =
+		|part: &str| !part.starts_with('.') && part.chars().all(|c| c.is_ascii_alphanumeric() || "._-:[]".contains(c));
+	if !is_safe(server_name) || !is_safe(media_id) {
+		bail!("{mxc} can not be stored at an archive");
+	}
+	Ok(format!("media/{server_name}/{media_id}"))
+}
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Maunium or ponies pack file to export
+	pack: PathBuf,
+
+	/// Path of the archive [default: <PACK>.tar.gz]
+	#[clap(short, long, value_name = "FILE")]
+	output: Option<PathBuf>
+}
+
+fn append(builder: &mut tar::Builder<GzEncoder<File>>, path: &str, data: &[u8]) -> std::io::Result<()> {
+	let mut header = tar::Header::new_gnu();
+	header.set_size(data.len() as u64);
+	header.set_mode(0o644);
+	builder.append_data(&mut header, path, data)
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let (_, pack) = read_pack(&opt.pack)
+		.await
+		.with_context(|| format!("failed to read stickerpack {:?}", opt.pack))?;
+	if pack.stickers.is_empty() {
+		bail!("stickerpack {:?} is empty", opt.pack);
+	}
+
+	// the same file can be used multiple times, e.g. as image and as thumbnail
+	let urls: Vec<Mxc> = unique_images(&pack).into_iter().map(|image| image.url.clone()).collect();
+	info!("download {} files of pack {:?}", urls.len(), pack.title);
+	let files: Vec<(String, _)> = stream::iter(urls)
+		.map(|mut mxc| {
+			let matrix = &config.matrix;
+			async move {
+				let path = media_path(&mxc)?;
+				let data = mxc
+					.fetch_data(matrix)
+					.await
+					.with_context(|| format!("failed to download {mxc}"))?;
+				anyhow::Ok((path, data))
+			}
+		})
+		.buffer_unordered(8)
+		.try_collect()
+		.await?;
+
+	let output = opt.output.unwrap_or_else(|| opt.pack.with_extension("tar.gz"));
+	write_archive(&output, pack, &files)?;
+	info!("saved archive to {output:?}");
+	Ok(())
+}
+
+/// save the pack and its files, stored at their [media_path], to the archive at `output`
+pub(crate) fn write_archive(
+	output: &Path,
+	pack: matrix::stickerpack::StickerPack,
+	files: &[(String, Arc<Vec<u8>>)]
+) -> anyhow::Result<()> {
+	let manifest = serde_json::to_vec_pretty(&ArchiveManifest {
+		version: ARCHIVE_VERSION,
+		pack
+	})?;
+	let file = File::create(output).with_context(|| format!("failed to create {output:?}"))?;
+	let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+	append(&mut builder, MANIFEST, &manifest)?;
+	for (path, data) in files {
+		append(&mut builder, path, data)?;
+	}
+	builder
+		.into_inner()?
+		.finish()?
+		.sync_all()
+		.with_context(|| format!("failed to write {output:?}"))?;
+	Ok(())
+}
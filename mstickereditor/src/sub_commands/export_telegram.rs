@@ -0,0 +1,118 @@
+use crate::{load_config_file, pack_file::read_pack};
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use log::{error, info};
+use mstickerlib::tg;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Maunium or ponies pack file to export
+	pack: PathBuf,
+
+	/// Telegram user id of the owner of the new sticker set.
+	/// The user must have started a chat with the bot before.
+	#[clap(long, value_name = "USER_ID")]
+	user_id: u64,
+
+	/// Short name of the sticker set, used at the url `t.me/addstickers/<NAME>` [default: id of the pack].
+	/// `_by_<bot username>` is appended, as required by telegram.
+	#[clap(long)]
+	name: Option<String>,
+
+	/// Title of the sticker set [default: title of the pack]
+	#[clap(long)]
+	title: Option<String>,
+
+	/// Emoji for stickers without emoji
+	#[clap(long, value_name = "EMOJI", default_value = "🙂")]
+	default_emoji: String
+}
+
+/// Create a valid sticker set name, ending with `_by_<bot>`.
+/// Telegram allows only letters, digits and single underscores, starting with a letter.
+pub(crate) fn set_name(name: &str, bot: &str) -> String {
+	let suffix = format!("_by_{bot}");
+	let mut set_name = String::new();
+	for c in name.strip_suffix(&suffix).unwrap_or(name).chars() {
+		let c = if c.is_ascii_alphanumeric() { c } else { '_' };
+		if (c == '_' || c.is_ascii_digit()) && set_name.is_empty() || c == '_' && set_name.ends_with('_') {
+			continue;
+		}
+		set_name.push(c);
+	}
+	let set_name = set_name.trim_end_matches('_');
+	let set_name = if set_name.is_empty() { "stickers" } else { set_name };
+	// at most 64 characters
+	let max_len = 64 - suffix.len();
+	format!(
+		"{}{suffix}",
+		set_name.get(..max_len).unwrap_or(set_name).trim_end_matches('_')
+	)
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let (_, mut pack) = read_pack(&opt.pack)
+		.await
+		.with_context(|| format!("failed to read stickerpack {:?}", opt.pack))?;
+	if pack.stickers.is_empty() {
+		bail!("stickerpack {:?} is empty", opt.pack);
+	}
+	if let Some(title) = opt.title {
+		pack.title = title;
+	}
+
+	let bot = tg::get_me(&config.telegram).await.context("Error connecting to Telegram")?;
+	let bot_name = bot
+		.username()
+		.as_deref()
+		.ok_or_else(|| anyhow!("telegram bot has no username"))?;
+	let name = set_name(opt.name.as_deref().unwrap_or(&pack.id), bot_name);
+	info!("export {} stickers to sticker set {name:?}", pack.stickers.len());
+
+	match tg::export_pack(
+		&pack,
+		&name,
+		opt.user_id,
+		&opt.default_emoji,
+		&config.telegram,
+		&config.matrix
+	)
+	.await
+	{
+		Ok(()) => {},
+		Err(errors) => {
+			let created = !errors.iter().any(|(index, _)| index.is_none()) && errors.len() < pack.stickers.len();
+			for (index, err) in errors {
+				let err = anyhow::Error::from(err);
+				let err = match index {
+					Some(index) => err.context(format!("failed to export sticker {:?}", pack.stickers[index].body)),
+					None => err.context("failed to create sticker set")
+				};
+				error!("{err:?}");
+			}
+			if !created {
+				bail!("sticker set {name:?} was not created");
+			}
+			bail!("sticker set https://t.me/addstickers/{name} is not complete");
+		}
+	}
+	info!("created sticker set https://t.me/addstickers/{name}");
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::set_name;
+
+	#[test]
+	fn sticker_set_name() {
+		assert_eq!(set_name("My Cats!", "bot"), "My_Cats_by_bot");
+		assert_eq!(set_name("_1cats__dogs_", "bot"), "cats_dogs_by_bot");
+		assert_eq!(set_name("cats_by_bot", "bot"), "cats_by_bot");
+		assert_eq!(set_name("", "bot"), "stickers_by_bot");
+		assert_eq!(set_name(&"a".repeat(80), "bot").len(), 64);
+	}
+}
@@ -0,0 +1,78 @@
+use crate::{load_config_file, pack_file::read_pack};
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::{error, info, warn};
+use mstickerlib::matrix::sticker_formats::whatsapp;
+use std::path::PathBuf;
+use tokio::fs;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Maunium or ponies pack file to export
+	pack: PathBuf,
+
+	/// Author of the pack, shown by WhatsApp
+	#[clap(long)]
+	author: String,
+
+	/// Title of the pack [default: title of the pack]
+	#[clap(long)]
+	title: Option<String>,
+
+	/// Emoji for stickers without emoji
+	#[clap(long, value_name = "EMOJI", default_value = "🙂")]
+	default_emoji: String,
+
+	/// Directory to save the `.wastickers` archives to
+	#[clap(short, long, value_name = "DIR", default_value = ".")]
+	output_dir: PathBuf
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let (_, mut pack) = read_pack(&opt.pack)
+		.await
+		.with_context(|| format!("failed to read stickerpack {:?}", opt.pack))?;
+	if pack.stickers.is_empty() {
+		bail!("stickerpack {:?} is empty", opt.pack);
+	}
+	if let Some(title) = opt.title {
+		pack.title = title;
+	}
+	info!("export {} stickers to whatsapp", pack.stickers.len());
+
+	let (archives, complete) = match whatsapp::to_wastickers(&pack, &opt.author, &opt.default_emoji, &config.matrix).await {
+		Ok(archives) => (archives, true),
+		Err((archives, errors)) => {
+			for (index, err) in errors {
+				let err = anyhow::Error::from(err);
+				let err = match index {
+					Some(index) => err.context(format!("failed to convert sticker {:?}", pack.stickers[index].body)),
+					None => err.context("failed to create whatsapp pack")
+				};
+				error!("{err:?}");
+			}
+			(archives, false)
+		}
+	};
+	std::fs::create_dir_all(&opt.output_dir).with_context(|| format!("failed to create dir {:?}", opt.output_dir))?;
+	for archive in &archives {
+		if archive.pack.stickers.len() < whatsapp::MIN_STICKERS {
+			warn!(
+				"pack {:?} has less than {} stickers and can not be added to WhatsApp",
+				archive.pack.name,
+				whatsapp::MIN_STICKERS
+			);
+		}
+		let path = opt.output_dir.join(format!("{}.wastickers", archive.pack.identifier));
+		info!("save {} stickers to {path:?}", archive.pack.stickers.len());
+		fs::write(&path, &archive.data)
+			.await
+			.with_context(|| format!("failed to write {path:?}"))?;
+	}
+	if !complete {
+		bail!("not all stickers of {:?} were exported", opt.pack);
+	}
+	Ok(())
+}
@@ -1,29 +1,41 @@
-use crate::{load_config_file, DATABASE_FILE};
+use crate::{
+	config::Config,
+	load_config_file,
+	pack_file::{to_json, Format}
+};
 use anyhow::{bail, Context};
-use clap::Parser;
+use clap::{Args, Parser};
 use log::{error, info, warn};
 use mstickerlib::{
-	database::FileDatabase,
-	matrix,
-	matrix::sticker_formats::maunium,
-	tg::{self, pack_url_to_name, ImportConfig}
+	database::DynDatabase,
+	tg::{self, ImportJournal, PackRef, StickerType}
+};
+use std::{
+	path::{Path, PathBuf},
+	process::exit
 };
-use std::{path::PathBuf, process::exit};
 use tokio::fs;
 
 #[derive(Debug, Parser)]
 pub struct Opt {
-	/// Pack url
+	/// Pack url, like `https://t.me/addstickers/<name>` or `https://t.me/addemoji/<name>`, or pack name
 	#[clap(required = true)]
 	packs: Vec<String>,
 
+	#[clap(flatten)]
+	import: ImportOpt
+}
+
+/// options for importing a pack, shared with the `bot` subcommand
+#[derive(Debug, Args)]
+pub struct ImportOpt {
 	/// Save stickers to disk
 	#[clap(short, long)]
 	save: bool,
 
 	/// Does not upload the sticker to Matrix
 	#[clap(short = 'd', long)]
-	dryrun: bool,
+	pub(crate) dryrun: bool,
 
 	/// Do not format video stickers.
 	/// The stickers can may not be shown by a matrix client.
@@ -34,81 +46,247 @@ pub struct Opt {
 	/// The stickers can may not be shown by a matrix client.
 	/// Lottie files will be unpack from zstd archive.
 	#[clap(long)]
-	keep_lottie: bool
+	keep_lottie: bool,
+
+	/// Also import the full-screen effect of premium stickers
+	#[clap(long)]
+	premium: bool,
+
+	/// Color of custom emoji, which adapt to the text color at telegram, as hex code like `#ffffff`.
+	/// By default the original color is kept.
+	#[clap(long, value_name = "COLOR", parse(try_from_str = parse_color))]
+	emoji_color: Option<[u8; 3]>,
+
+	/// Do not resume a previous interrupted import of the packs and start again
+	#[clap(long)]
+	restart: bool,
+
+	/// Directory to save the stickerpacks and stickers to
+	#[clap(short, long, value_name = "DIR", default_value = ".")]
+	pub(crate) output_dir: PathBuf,
+
+	/// File name of the stickerpacks.
+	/// `{name}`, `{title}` and `{id}` are replaced by the values of the pack.
+	#[clap(long, value_name = "TEMPLATE", default_value = "{name}.json")]
+	file_name: String
+}
+
+/// Replace the characters, which are not allowed at file names of common file systems.
+/// Empty values and values like `..` are replaced by `_`.
+fn escape_file_name(value: &str) -> String {
+	let value: String = value
+		.chars()
+		.map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+		.collect();
+	// windows drops trailing dots and spaces
+	let value = value.trim_end_matches(['.', ' ']);
+	match value {
+		"" => "_".to_owned(),
+		value => value.to_owned()
+	}
+}
+
+/// fill the `{name}`, `{title}` and `{id}` placeholders of the file name template
+pub(crate) fn file_name(template: &str, name: &str, title: &str, id: &str) -> String {
+	// the title is chosen by the pack creator and can contain path separators
+	template
+		.replace("{name}", &escape_file_name(name))
+		.replace("{title}", &escape_file_name(title))
+		.replace("{id}", &escape_file_name(id))
+}
+
+/// parse a hex color like `#ff8000`
+pub(crate) fn parse_color(color: &str) -> Result<[u8; 3], String> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	let channel = |i: usize| {
+		hex.get(i..i + 2)
+			.and_then(|channel| u8::from_str_radix(channel, 16).ok())
+			.ok_or_else(|| format!("{color:?} is not a hex color like #ffffff"))
+	};
+	if hex.len() != 6 {
+		return Err(format!("{color:?} is not a hex color like #ffffff"));
+	}
+	Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Result of [import_pack]
+pub(crate) struct ImportedPack {
+	/// name of the telegram pack
+	pub name: String,
+	/// title of the telegram pack
+	pub title: String,
+	/// number of imported stickers
+	pub stickers: usize,
+	/// all stickers of the pack were imported
+	pub complete: bool,
+	/// path of the saved stickerpack; `None` if no sticker could be imported
+	pub path: Option<PathBuf>
+}
+
+/// Import the telegram pack `pack` and save it to the output dir.
+/// Errors of single stickers are logged and do not fail the import.
+pub(crate) async fn import_pack(
+	pack: &str,
+	opt: &ImportOpt,
+	config: &Config,
+	database: Option<&dyn DynDatabase>,
+	journal_dir: &Path
+) -> anyhow::Result<ImportedPack> {
+	info!("loading data for {pack}");
+	let tg_pack = tg::StickerPack::get(pack, &config.telegram)
+		.await
+		.with_context(|| format!("failed to get telegram sticker pack {pack:?}"))?;
+	let journal_path = journal_dir.join(format!("{}.jsonl", tg_pack.name()));
+	if opt.restart && journal_path.exists() {
+		fs::remove_file(&journal_path)
+			.await
+			.with_context(|| format!("failed to remove journal {journal_path:?}"))?;
+	}
+	let journal = ImportJournal::open(&journal_path)
+		.await
+		.with_context(|| format!("failed to open journal {journal_path:?}"))?;
+	let mut import_config = config.import_config(database, opt.dryrun);
+	import_config.journal = Some(&journal);
+	import_config.keep_webm = opt.keep_webm;
+	import_config.keep_lottie = opt.keep_lottie;
+	import_config.repaint_color = opt.emoji_color;
+	import_config.premium_animations = opt.premium;
+	let import_config = import_config;
+
+	let matrix_pack = tg_pack.import(&config.telegram, &config.matrix, &import_config).await;
+	let (matrix_pack, complete) = match matrix_pack {
+		Ok(pack) => {
+			journal
+				.remove()
+				.await
+				.with_context(|| format!("failed to remove journal {journal_path:?}"))?;
+			(pack, true)
+		},
+		Err((matrix_pack, errors)) => {
+			for (index, err) in errors {
+				let err = anyhow::Error::from(err).context(format!("failed to import sticker {index} from pack {pack:?}"));
+				error!("{err:?}");
+			}
+			warn!("Sticker pack {} is not complete", tg_pack.name());
+			(matrix_pack, false)
+		}
+	};
+	let mut imported = ImportedPack {
+		name: tg_pack.name().to_owned(),
+		title: matrix_pack.title.clone(),
+		stickers: matrix_pack.stickers.len(),
+		complete,
+		path: None
+	};
+	if matrix_pack.stickers.is_empty() {
+		return Ok(imported);
+	}
+	if opt.save {
+		info!("save stickers of pack {} to disk", tg_pack.name());
+		let dir = opt
+			.output_dir
+			.join("stickers")
+			.join(&matrix_pack.tg_pack.as_ref().unwrap().name);
+		std::fs::create_dir_all(&dir).with_context(|| format!("failed to create dir {dir:?}"))?;
+		for sticker in &matrix_pack.stickers {
+			{
+				let index = sticker.tg_sticker.as_ref().unwrap().index.unwrap(); //should exist, since we have import the sticker from telegram right now
+				let extension = sticker.image.meta_data.mimetype.split('/').last().unwrap();
+				let path = dir.join(format!("{index:03}.{extension}"));
+				let data = match sticker.image.url.data() {
+					Some(data) => data.clone(),
+					// sticker was imported by a previous run, so it must be downloaded from matrix again
+					None => sticker
+						.image
+						.url
+						.clone()
+						.fetch_data(&config.matrix)
+						.await
+						.with_context(|| format!("failed to download sticker {index} from matrix"))?
+				};
+				fs::write(&path, data.as_ref())
+					.await
+					.with_context(|| format!("failed to save sticker to {path:?}"))?;
+			}
+		}
+	}
+	let path = opt
+		.output_dir
+		.join(file_name(&opt.file_name, tg_pack.name(), &matrix_pack.title, &matrix_pack.id));
+	// maunium packs do not support emoticons
+	let format = match tg_pack.sticker_type() {
+		StickerType::CustomEmoji => Format::Ponies,
+		_ => Format::Maunium
+	};
+	info!("save stickerpack to {:?}", path);
+	fs::write(&path, to_json(format, matrix_pack)?).await?;
+	imported.path = Some(path);
+	Ok(imported)
 }
 
 #[tokio::main]
 pub async fn run(mut opt: Opt) -> anyhow::Result<()> {
 	let config = load_config_file()?;
 
-	if !opt.dryrun {
-		matrix::whoami(&config.matrix)
-			.await
-			.expect("Error connecting to Matrix homeserver");
-	}
+	config.check_matrix(opt.import.dryrun).await?;
 	let mut packs: Vec<String> = Vec::new();
 	while let Some(pack) = opt.packs.pop() {
-		let name = pack_url_to_name(&pack).unwrap_or_else(|err| {
+		let pack: PackRef = pack.parse().unwrap_or_else(|err| {
 			eprintln!("{err}");
 			exit(1)
 		});
-		packs.push(name.to_owned());
+		packs.push(pack.name().to_owned());
 	}
-	let database = FileDatabase::new(&*DATABASE_FILE).await?;
-	let mut import_config = ImportConfig::default();
-	import_config.database = Some(&database);
-	import_config.dry_run = opt.dryrun;
-	import_config.keep_webm = opt.keep_webm;
-	import_config.keep_lottie = opt.keep_lottie;
-	import_config.animation_format = config.sticker;
-	let import_config = import_config;
+	std::fs::create_dir_all(&opt.import.output_dir)
+		.with_context(|| format!("failed to create dir {:?}", opt.import.output_dir))?;
+	let database = config.database.open(&config.data_dir).await?;
+	let journal_dir = config.data_dir.join("journal");
+	std::fs::create_dir_all(&journal_dir).with_context(|| format!("failed to create dir {journal_dir:?}"))?;
 	let mut empty_packs = Vec::new();
 
 	for pack in packs {
-		info!("loading data for {pack}");
-		let tg_pack = tg::StickerPack::get(&pack, &config.telegram)
-			.await
-			.with_context(|| format!("failed to get telegram sticker pack {pack:?}"))?;
-		let matrix_pack = tg_pack.import(&config.telegram, &config.matrix, &import_config).await;
-		let matrix_pack = match matrix_pack {
-			Ok(pack) => pack,
-			Err((matrix_pack, errors)) => {
-				for (index, err) in errors {
-					let err =
-						anyhow::Error::from(err).context(format!("failed to import sticker {index} from pack {pack:?}"));
-					error!("{err:?}");
-				}
-				warn!("Sticker pack {} is not complete", tg_pack.name());
-				empty_packs.push(tg_pack.name().to_owned());
-				matrix_pack
-			}
-		};
-		if matrix_pack.stickers.is_empty() {
-			error!("Sticker pack {} is empty", tg_pack.name());
-			continue;
+		let imported = import_pack(&pack, &opt.import, &config, database.as_deref(), &journal_dir).await?;
+		if imported.path.is_none() {
+			error!("Sticker pack {} is empty", imported.name);
 		}
-		if opt.save {
-			info!("save stickers of pack {} to disk", tg_pack.name());
-			let dir = format!("./stickers/{}/", matrix_pack.tg_pack.as_ref().unwrap().name);
-			std::fs::create_dir_all(&dir).with_context(|| format!("failed to create dir {dir:?}"))?;
-			for sticker in &matrix_pack.stickers {
-				{
-					let index = sticker.tg_sticker.as_ref().unwrap().index.unwrap(); //should exist, since we have import the sticker from telegram right now
-					let extension = sticker.image.meta_data.mimetype.split('/').last().unwrap();
-					let path = format!("{dir}/{index:03}.{extension}");
-					fs::write(&path, sticker.image.url.data().as_ref().unwrap().as_ref())
-						.await
-						.with_context(|| format!("failed to save sticker to {path:?}"))?;
-				}
-			}
+		if !imported.complete {
+			empty_packs.push(imported.name);
 		}
-		let matrix_pack: maunium::StickerPack = matrix_pack.into();
-		let path: PathBuf = format!("./{}.json", tg_pack.name()).into();
-		info!("save stickerpack to {:?}", path);
-		fs::write(path, serde_json::to_string(&matrix_pack)?).await?;
 	}
 	if !empty_packs.is_empty() {
 		bail!("The following packs are empty {empty_packs:?}");
 	}
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{file_name, parse_color};
+
+	#[test]
+	fn file_name_template() {
+		assert_eq!(file_name("{name}.json", "Cats", "Cute/Cats", "tg_name_Cats"), "Cats.json");
+		assert_eq!(
+			file_name("{title} ({id}).json", "Cats", "Cute/Cats", "tg_name_Cats"),
+			"Cute_Cats (tg_name_Cats).json"
+		);
+		assert_eq!(file_name("{title}", "Cats", "..", "tg_name_Cats"), "_");
+		assert_eq!(file_name("{title}.json", "Cats", "", "tg_name_Cats"), "_.json");
+		assert_eq!(
+			file_name("{title}.json", "Cats", "a:b*c?\"<d>|\n.", "tg_name_Cats"),
+			"a_b_c___d___.json"
+		);
+		assert_eq!(
+			file_name("{title}.json", "Cats", "../../etc", "tg_name_Cats"),
+			".._.._etc.json"
+		);
+	}
+
+	#[test]
+	fn color() {
+		assert_eq!(parse_color("#ff8000"), Ok([255, 128, 0]));
+		assert_eq!(parse_color("00Ff10"), Ok([0, 255, 16]));
+		assert!(parse_color("#fff").is_err());
+		assert!(parse_color("#gg0000").is_err());
+	}
+}
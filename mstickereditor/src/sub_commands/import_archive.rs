@@ -0,0 +1,196 @@
+use super::{
+	export::{media_path, ArchiveManifest, ARCHIVE_VERSION, MANIFEST},
+	import::file_name
+};
+use crate::{
+	load_config_file,
+	pack_file::{replace_urls, to_json, to_upload, unique_images, Format}
+};
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use flate2::read::GzDecoder;
+use log::info;
+use mstickerlib::{
+	database::DynDatabase,
+	matrix::{self, stickerpack::StickerPack, Mxc}
+};
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::Read,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+use tokio::fs;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Archive created by `export`
+	archive: PathBuf,
+
+	/// Does not upload the sticker to Matrix
+	#[clap(short = 'd', long)]
+	dryrun: bool,
+
+	/// Format of the created stickerpack
+	#[clap(long, arg_enum, default_value = "maunium")]
+	format: Format,
+
+	/// Directory to save the stickerpack to
+	#[clap(short, long, value_name = "DIR", default_value = ".")]
+	output_dir: PathBuf,
+
+	/// File name of the stickerpack.
+	/// `{name}`, `{title}` and `{id}` are replaced by the values of the pack; `{name}` is the name of the archive.
+	#[clap(long, value_name = "TEMPLATE", default_value = "{name}.json")]
+	file_name: String
+}
+
+/// read the manifest and all files of an archive
+fn read_archive(path: &Path) -> anyhow::Result<(ArchiveManifest, HashMap<String, Vec<u8>>)> {
+	let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+	let mut manifest = None;
+	let mut files = HashMap::new();
+	for entry in archive.entries()? {
+		let mut entry = entry?;
+		let entry_path = entry.path()?.to_string_lossy().into_owned();
+		let mut data = Vec::new();
+		entry.read_to_end(&mut data)?;
+		if entry_path == MANIFEST {
+			manifest = Some(serde_json::from_slice::<ArchiveManifest>(&data).context("invalid manifest")?);
+		} else {
+			files.insert(entry_path, data);
+		}
+	}
+	let manifest = manifest.ok_or_else(|| anyhow!("archive does not contain a {MANIFEST}"))?;
+	if manifest.version > ARCHIVE_VERSION {
+		bail!("archive version {} is not supported, update mstickereditor", manifest.version);
+	}
+	Ok((manifest, files))
+}
+
+/// Upload the files of the archive and replace the urls of the pack by the new ones.
+/// Files uploaded before are only reused, if they are stored at the configured homeserver.
+async fn upload_files(
+	mut pack: StickerPack,
+	mut files: HashMap<String, Vec<u8>>,
+	matrix: &matrix::Config,
+	database: Option<&dyn DynDatabase>,
+	dryrun: bool
+) -> anyhow::Result<StickerPack> {
+	// upload each file once and remember the new url
+	let mut new_urls: HashMap<String, Mxc> = HashMap::new();
+	for image in unique_images(&pack) {
+		let path = media_path(&image.url)?;
+		let data = files
+			.remove(&path)
+			.ok_or_else(|| anyhow!("archive does not contain file {path:?} of {}", image.url))?;
+		let upload = to_upload(image, Arc::new(data))?;
+		let new_mxc = if dryrun {
+			Mxc::new(image.url.url().to_owned(), Some(upload.data))
+		} else {
+			info!("upload {path}");
+			let (new_mxc, has_uploaded) = upload
+				.upload(matrix, database)
+				.await
+				.with_context(|| format!("failed to upload {path:?}"))?;
+			if !has_uploaded {
+				info!("  upload skipped; file with this hash was already uploaded");
+			}
+			new_mxc
+		};
+		new_urls.insert(image.url.url().to_owned(), new_mxc);
+	}
+	replace_urls(&mut pack, &new_urls);
+	Ok(pack)
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+
+	config.check_matrix(opt.dryrun).await?;
+	let (manifest, files) =
+		read_archive(&opt.archive).with_context(|| format!("failed to read archive {:?}", opt.archive))?;
+	let database = config.database.open(&config.data_dir).await?;
+	let pack = upload_files(manifest.pack, files, &config.matrix, database.as_deref(), opt.dryrun).await?;
+
+	std::fs::create_dir_all(&opt.output_dir).with_context(|| format!("failed to create dir {:?}", opt.output_dir))?;
+	let name = opt
+		.archive
+		.file_name()
+		.and_then(|name| name.to_str())
+		.map(|name| name.trim_end_matches(".tar.gz"))
+		.unwrap_or(&pack.id)
+		.to_owned();
+	let path = opt.output_dir.join(file_name(&opt.file_name, &name, &pack.title, &pack.id));
+	info!("save stickerpack to {:?}", path);
+	fs::write(path, to_json(opt.format, pack)?).await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{read_archive, upload_files};
+	use crate::sub_commands::export::{write_archive, MANIFEST};
+	use mstickerlib::matrix::{self, stickerpack::StickerPack};
+	use serde_json::json;
+	use std::sync::Arc;
+
+	fn archive_pack(urls: &[&str]) -> StickerPack {
+		let stickers: Vec<_> = urls
+			.iter()
+			.map(|url| {
+				json!({
+					"body": "cat",
+					"image": { "url": url, "meta_data": { "w": 512, "h": 512, "size": 3, "mimetype": "image/webp" } },
+					"thumbnail": { "url": url, "meta_data": { "w": 512, "h": 512, "size": 3, "mimetype": "image/webp" } },
+					"emoticon": "cat",
+					"emoji": ["😺"],
+					"tg_sticker": null
+				})
+			})
+			.collect();
+		serde_json::from_value(json!({ "title": "Cats", "id": "cats", "tg_pack": null, "stickers": stickers })).unwrap()
+	}
+
+	#[tokio::test]
+	async fn round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let matrix = matrix::Config {
+			homeserver_url: "none".to_owned(),
+			user: "@alice:example.org".to_owned(),
+			access_token: "none".to_owned()
+		};
+
+		let path = dir.path().join("cats.tar.gz");
+		let files = [("media/example.org/cat".to_owned(), Arc::new(b"cat".to_vec()))];
+		write_archive(&path, archive_pack(&["mxc://example.org/cat"]), &files).unwrap();
+		let (manifest, files) = read_archive(&path).unwrap();
+		assert_eq!(manifest.version, 1);
+		assert_eq!(manifest.pack.stickers[0].emoticon.as_deref(), Some("cat"));
+		assert_eq!(files.keys().collect::<Vec<_>>(), ["media/example.org/cat"]);
+		// the manifest is not part of the files
+		assert!(!files.contains_key(MANIFEST));
+		let pack = upload_files(manifest.pack, files, &matrix, None, true).await.unwrap();
+		let sticker = &pack.stickers[0];
+		assert_eq!(sticker.image.url.url(), "mxc://example.org/cat");
+		assert_eq!(sticker.emoji, ["😺"]);
+
+		// the file of the url is missing
+		let path = dir.path().join("missing.tar.gz");
+		write_archive(&path, archive_pack(&["mxc://example.org/dog"]), &[]).unwrap();
+		let (manifest, files) = read_archive(&path).unwrap();
+		assert!(upload_files(manifest.pack, files, &matrix, None, true).await.is_err());
+
+		// urls, which would point outside of the media dir of the archive
+		for url in ["mxc://../cat", "mxc://example.org/..", "mxc://example.org/.hidden"] {
+			let path = dir.path().join("outside.tar.gz");
+			let files = [("cat".to_owned(), Arc::new(b"cat".to_vec()))];
+			write_archive(&path, archive_pack(&[url]), &files).unwrap();
+			let (manifest, files) = read_archive(&path).unwrap();
+			let err = upload_files(manifest.pack, files, &matrix, None, true).await.unwrap_err();
+			assert!(err.to_string().contains("can not be stored at an archive"), "{url}: {err}");
+		}
+	}
+}
@@ -0,0 +1,83 @@
+use super::import_dir::{import_local_pack, LocalImportOpt};
+use crate::load_config_file;
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::info;
+use mstickerlib::local::LocalPack;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Directory of the chat export, containing the `result.json`.
+	/// The chat history must be exported as json.
+	dir: PathBuf,
+
+	/// Only import the stickers of the chats with this name. Can be used multiple times.
+	#[clap(long = "chat", value_name = "NAME")]
+	chats: Vec<String>,
+
+	#[clap(flatten)]
+	import: LocalImportOpt
+}
+
+/// Name the pack files after the chats, given as title and id.
+/// Chats with the same title get their id appended, so they do not overwrite the file of each other.
+fn pack_names(chats: &[(&str, &str)]) -> Vec<String> {
+	chats
+		.iter()
+		.map(|(title, id)| {
+			if chats.iter().filter(|(other, _)| other == title).count() > 1 {
+				format!("{title} ({id})")
+			} else {
+				(*title).to_owned()
+			}
+		})
+		.collect()
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let mut packs = LocalPack::read_chat_export(&opt.dir)
+		.await
+		.with_context(|| format!("failed to read chat export {:?}", opt.dir))?;
+	if !opt.chats.is_empty() {
+		packs.retain(|pack| opt.chats.iter().any(|chat| chat == pack.title()));
+	}
+	if packs.is_empty() {
+		bail!("no stickers found at chat export {:?}", opt.dir);
+	}
+
+	config.check_matrix(opt.import.dryrun).await?;
+	std::fs::create_dir_all(&opt.import.output_dir)
+		.with_context(|| format!("failed to create dir {:?}", opt.import.output_dir))?;
+	let database = config.database.open(&config.data_dir).await?;
+	let mut incomplete_packs = Vec::new();
+
+	let chats: Vec<(&str, &str)> = packs.iter().map(|pack| (pack.title().as_str(), pack.id().as_str())).collect();
+	let names = pack_names(&chats);
+	for (pack, name) in packs.iter().zip(&names) {
+		info!("import {} stickers of chat {:?}", pack.stickers().len(), pack.title());
+		if !import_local_pack(pack, name, &opt.import, &config, database.as_deref()).await? {
+			incomplete_packs.push(pack.title().to_owned());
+		}
+	}
+	if !incomplete_packs.is_empty() {
+		bail!("The following packs are not complete {incomplete_packs:?}");
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::pack_names;
+
+	#[test]
+	fn chat_names() {
+		assert_eq!(pack_names(&[("Alice", "tg_chat_1"), ("Bob", "tg_chat_2")]), ["Alice", "Bob"]);
+		assert_eq!(
+			pack_names(&[("Alice", "tg_chat_1"), ("Bob", "tg_chat_2"), ("Alice", "tg_chat_3")]),
+			["Alice (tg_chat_1)", "Bob", "Alice (tg_chat_3)"]
+		);
+	}
+}
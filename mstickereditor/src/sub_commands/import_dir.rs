@@ -0,0 +1,199 @@
+use super::import::file_name;
+use crate::{
+	config::Config,
+	load_config_file,
+	pack_file::{to_json, Format}
+};
+use anyhow::{bail, Context};
+use clap::{Args, Parser};
+use log::{error, info, warn};
+use mstickerlib::{
+	database::DynDatabase,
+	local::{LocalPack, Manifest}
+};
+use std::path::PathBuf;
+use tokio::fs;
+
+const MANIFEST: &str = "manifest.toml";
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Directories containing the stickers.
+	/// Each directory is imported as one pack and can contain a `manifest.toml`.
+	#[clap(required = true)]
+	dirs: Vec<PathBuf>,
+
+	#[clap(flatten)]
+	import: LocalImportOpt
+}
+
+/// options for importing local packs, shared with the `import-chat-export` subcommand
+#[derive(Debug, Args)]
+pub struct LocalImportOpt {
+	/// Does not upload the sticker to Matrix
+	#[clap(short = 'd', long)]
+	pub(crate) dryrun: bool,
+
+	/// Do not format video stickers.
+	/// The stickers can may not be shown by a matrix client.
+	#[clap(long)]
+	keep_webm: bool,
+
+	/// Do not format animated stickers.
+	/// The stickers can may not be shown by a matrix client.
+	#[clap(long)]
+	keep_lottie: bool,
+
+	/// Directory to save the stickerpacks to
+	#[clap(short, long, value_name = "DIR", default_value = ".")]
+	pub(crate) output_dir: PathBuf,
+
+	/// Format of the created stickerpacks.
+	/// Maunium packs keep the emoji and shortcodes of the stickers, but the maunium stickerpicker does not use them.
+	#[clap(long, arg_enum, default_value = "maunium")]
+	format: Format,
+
+	/// File name of the stickerpacks.
+	/// `{name}`, `{title}` and `{id}` are replaced by the values of the pack;
+	/// `{name}` is the name of the directory or chat.
+	#[clap(long, value_name = "TEMPLATE", default_value = "{name}.json")]
+	file_name: String
+}
+
+/// Import a local pack and save it to the output dir in the selected format.
+/// Errors of single stickers are logged; return `false`, if the pack is not complete.
+pub(crate) async fn import_local_pack(
+	local_pack: &LocalPack,
+	name: &str,
+	opt: &LocalImportOpt,
+	config: &Config,
+	database: Option<&dyn DynDatabase>
+) -> anyhow::Result<bool> {
+	if local_pack.stickers().is_empty() {
+		error!("no stickers found for pack {}", local_pack.title());
+		return Ok(false);
+	}
+	let mut import_config = config.import_config(database, opt.dryrun);
+	import_config.keep_webm = opt.keep_webm;
+	import_config.keep_lottie = opt.keep_lottie;
+	let import_config = import_config;
+
+	let (matrix_pack, complete) = match local_pack.import(&config.matrix, &import_config).await {
+		Ok(pack) => (pack, true),
+		Err((matrix_pack, errors)) => {
+			for (index, err) in errors {
+				let path = local_pack.stickers()[index].path();
+				let err = anyhow::Error::from(err).context(format!("failed to import sticker {path:?}"));
+				error!("{err:?}");
+			}
+			warn!("Sticker pack {} is not complete", local_pack.title());
+			(matrix_pack, false)
+		}
+	};
+	if matrix_pack.stickers.is_empty() {
+		return Ok(complete);
+	}
+
+	let path = opt
+		.output_dir
+		.join(file_name(&opt.file_name, name, &matrix_pack.title, &matrix_pack.id));
+	info!("save stickerpack to {:?}", path);
+	fs::write(path, to_json(opt.format, matrix_pack)?).await?;
+	Ok(complete)
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+
+	config.check_matrix(opt.import.dryrun).await?;
+	std::fs::create_dir_all(&opt.import.output_dir)
+		.with_context(|| format!("failed to create dir {:?}", opt.import.output_dir))?;
+	let database = config.database.open(&config.data_dir).await?;
+	let mut incomplete_packs = Vec::new();
+
+	for dir in opt.dirs {
+		let manifest_path = dir.join(MANIFEST);
+		let manifest: Option<Manifest> = match manifest_path.exists() {
+			true => Some(
+				toml::from_str(&fs::read_to_string(&manifest_path).await?)
+					.with_context(|| format!("failed to parse manifest {manifest_path:?}"))?
+			),
+			false => None
+		};
+		let local_pack = LocalPack::read(&dir, manifest)
+			.await
+			.with_context(|| format!("failed to read stickers from {dir:?}"))?;
+		let name = dir
+			.canonicalize()?
+			.file_name()
+			.and_then(|name| name.to_str())
+			.unwrap_or(local_pack.id())
+			.to_owned();
+		if !import_local_pack(&local_pack, &name, &opt.import, &config, database.as_deref()).await? {
+			incomplete_packs.push(local_pack.title().to_owned());
+		}
+	}
+	if !incomplete_packs.is_empty() {
+		bail!("The following packs are not complete {incomplete_packs:?}");
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{import_local_pack, LocalImportOpt};
+	use crate::{config::Config, database::DatabaseConfig, pack_file::Format};
+	use mstickerlib::{image::AnimationFormat, local::LocalPack, matrix, tg};
+	use std::fs;
+
+	/// png header with the given size
+	fn png(width: u32, height: u32) -> Vec<u8> {
+		let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+		data.extend(width.to_be_bytes());
+		data.extend(height.to_be_bytes());
+		data
+	}
+
+	#[tokio::test]
+	async fn keep_emoji() {
+		let dir = tempfile::tempdir().unwrap();
+		let stickers_dir = dir.path().join("cats");
+		fs::create_dir(&stickers_dir).unwrap();
+		fs::write(stickers_dir.join("01_happy cat 😺.png"), png(512, 512)).unwrap();
+		let config = Config {
+			telegram: tg::Config {
+				bot_key: "none".to_owned()
+			},
+			matrix: matrix::Config {
+				homeserver_url: "none".to_owned(),
+				user: "none".to_owned(),
+				access_token: "none".to_owned()
+			},
+			sticker: AnimationFormat::Webp,
+			database: DatabaseConfig::None,
+			data_dir: dir.path().to_owned()
+		};
+		let local_pack = LocalPack::read(&stickers_dir, None).await.unwrap();
+		for (format, sticker) in [(Format::Maunium, "/stickers/0"), (Format::Ponies, "/images/happy_cat")] {
+			let opt = LocalImportOpt {
+				dryrun: true,
+				keep_webm: false,
+				keep_lottie: false,
+				output_dir: dir.path().to_owned(),
+				file_name: "{name}.json".to_owned(),
+				format
+			};
+			assert!(import_local_pack(&local_pack, "cats", &opt, &config, None).await.unwrap());
+			let json: serde_json::Value = serde_json::from_slice(&fs::read(dir.path().join("cats.json")).unwrap()).unwrap();
+			let sticker = json.pointer(sticker).unwrap();
+			match format {
+				Format::Maunium => {
+					assert_eq!(sticker["dev.luckyturtle.mstickereditor.emoticon"], "happy_cat");
+					assert_eq!(sticker["dev.luckyturtle.mstickereditor.emoji"], serde_json::json!(["😺"]));
+				},
+				Format::Ponies => assert_eq!(sticker["dev.luckyturtle.mstickereditor.emoji"], serde_json::json!(["😺"]))
+			}
+		}
+	}
+}
@@ -0,0 +1,167 @@
+use crate::config::config_file_path;
+use anyhow::{bail, Context};
+use clap::Parser;
+use log::{error, info, warn};
+use mstickerlib::{matrix, tg};
+use std::{
+	fs,
+	io::{self, Write as _}
+};
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Overwrite an existing config file
+	#[clap(short, long)]
+	force: bool,
+
+	/// Do not check the entered credentials against Telegram and Matrix
+	#[clap(long)]
+	skip_validation: bool
+}
+
+/// Ask the user for a value, use `default` if the answer is empty.
+fn prompt(question: &str, default: Option<&str>) -> anyhow::Result<String> {
+	loop {
+		match default {
+			Some(default) => print!("{question} [{default}]: "),
+			None => print!("{question}: ")
+		}
+		io::stdout().flush()?;
+		let mut answer = String::new();
+		if io::stdin().read_line(&mut answer)? == 0 {
+			bail!("unexpected end of input");
+		}
+		let answer = answer.trim();
+		if !answer.is_empty() {
+			return Ok(answer.to_owned());
+		}
+		if let Some(default) = default {
+			return Ok(default.to_owned());
+		}
+	}
+}
+
+/// quote and escape a toml string
+fn toml_string(value: &str) -> String {
+	// json strings are valid toml basic strings
+	serde_json::to_string(value).unwrap()
+}
+
+async fn telegram(opt: &Opt) -> anyhow::Result<tg::Config> {
+	loop {
+		let config = tg::Config {
+			bot_key: prompt("Telegram bot key (create one by writing to @BotFather)", None)?
+		};
+		if opt.skip_validation {
+			return Ok(config);
+		}
+		match tg::get_me(&config).await {
+			Ok(bot) => {
+				info!(
+					"bot key belongs to @{}",
+					bot.username().as_deref().unwrap_or(bot.first_name())
+				);
+				return Ok(config);
+			},
+			Err(err) => error!("{:?}", anyhow::Error::from(err).context("invalid bot key"))
+		}
+	}
+}
+
+async fn matrix(opt: &Opt) -> anyhow::Result<matrix::Config> {
+	loop {
+		let mut config = matrix::Config {
+			user: prompt("Matrix user", None)?,
+			homeserver_url: prompt("Matrix homeserver url", Some("https://matrix-client.matrix.org"))?,
+			access_token: prompt("Matrix access token", None)?
+		};
+		if opt.skip_validation {
+			return Ok(config);
+		}
+		match matrix::whoami(&config).await {
+			Ok(whoami) => {
+				if whoami.user_id() != &config.user {
+					warn!(
+						"access token belongs to {}, not to {}; use {0} as user",
+						whoami.user_id(),
+						config.user
+					);
+					config.user = whoami.user_id().to_owned();
+				}
+				return Ok(config);
+			},
+			Err(err) => error!("{:?}", anyhow::Error::from(err).context("invalid matrix credentials"))
+		}
+	}
+}
+
+/// Ask for the `[sticker]` section and return it as toml.
+fn sticker() -> anyhow::Result<String> {
+	#[cfg(feature = "lottie")]
+	loop {
+		match prompt("Format of animated stickers (webp/gif)", Some("webp"))?.as_str() {
+			"webp" => break,
+			"gif" => loop {
+				let color = prompt("Color of semitransparent pixels of gifs (r,g,b)", Some("0,0,0"))?;
+				let color: Result<Vec<u8>, _> = color.split(',').map(|value| value.trim().parse::<u8>()).collect();
+				match color.as_deref() {
+					Ok([r, g, b]) => {
+						return Ok(format!(
+							"# format to which animated stickers are converted: \"webp\" or \"gif\"\n\
+							animation_format = \"gif\"\n\
+							# color used for semitransparent pixels of gifs\n\
+							transparent_color = {{ r = {r}, g = {g}, b = {b}, a = true }}\n"
+						));
+					},
+					_ => error!("color must be three numbers between 0 and 255, separated by comma")
+				}
+			},
+			_ => error!("unsupported format")
+		}
+	}
+	Ok("# format to which animated stickers are converted: \"webp\" or \"gif\"\nanimation_format = \"webp\"\n".to_owned())
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let path = config_file_path();
+	if path.exists() && !opt.force {
+		bail!("config file {path:?} does already exist, use `--force` to overwrite it");
+	}
+	let telegram = telegram(&opt).await?;
+	let matrix = matrix(&opt).await?;
+	let sticker = sticker()?;
+	let config = format!(
+		"# config of mstickereditor, see https://github.com/LuckyTurtleDev/mstickereditor#configuration\n\
+		\n\
+		[telegram]\n\
+		# key of the telegram bot, used to download the stickers\n\
+		bot_key = {}\n\
+		\n\
+		[matrix]\n\
+		# account, to which the stickers are uploaded\n\
+		user = {}\n\
+		homeserver_url = {}\n\
+		access_token = {}\n\
+		\n\
+		[sticker]\n\
+		{sticker}",
+		toml_string(&telegram.bot_key),
+		toml_string(&matrix.user),
+		toml_string(&matrix.homeserver_url),
+		toml_string(&matrix.access_token)
+	);
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir).with_context(|| format!("failed to create dir {dir:?}"))?;
+	}
+	fs::write(&path, config).with_context(|| format!("failed to write config file {path:?}"))?;
+	#[cfg(unix)]
+	{
+		// the config file contains secrets
+		use std::os::unix::fs::PermissionsExt as _;
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+			.with_context(|| format!("failed to set permissions of config file {path:?}"))?;
+	}
+	info!("config file was saved to {path:?}");
+	Ok(())
+}
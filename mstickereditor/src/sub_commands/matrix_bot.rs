@@ -0,0 +1,223 @@
+use crate::{config::Config, load_config_file};
+use anyhow::Context;
+use clap::Parser;
+use log::{error, info, warn};
+use mstickerlib::{
+	database::DynDatabase,
+	matrix::{self, room},
+	tg
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// milliseconds to wait for new events at each sync
+const SYNC_TIMEOUT: u64 = 30_000;
+/// delay after a failed sync, before trying again
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// maximum number of failed stickers listed at a reply
+const MAX_LISTED_ERRORS: usize = 10;
+/// notice sent to encrypted rooms, since the bot can not read their messages
+const ENCRYPTED_NOTICE: &str =
+	"This room is encrypted. mstickereditor does not support encryption, so pack links sent here can not be read. Invite me to an unencrypted room instead.";
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Id of a room, like `!abc:example.org`, where the bot imports packs. Can be used multiple times.
+	/// Invites to these rooms are accepted.
+	#[clap(long = "room", value_name = "ROOM_ID", required = true)]
+	rooms: Vec<String>,
+
+	/// Also import the full-screen effect of premium stickers
+	#[clap(long)]
+	premium: bool,
+
+	/// Color of custom emoji, which adapt to the text color at telegram, as hex code like `#ffffff`.
+	/// By default the original color is kept.
+	#[clap(long, value_name = "COLOR", parse(try_from_str = super::import::parse_color))]
+	emoji_color: Option<[u8; 3]>
+}
+
+/// Remove the quote of the original message from a reply,
+/// so links of the original message are not imported again.
+fn strip_reply_fallback(body: &str) -> String {
+	body.lines()
+		.skip_while(|line| line.starts_with('>'))
+		// the fallback is separated from the reply by an empty line
+		.skip_while(|line| line.trim().is_empty())
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// import a pack, publish it as room emotes and return the summary for the room
+/// Tell the room, that the bot can not read its messages, if it is encrypted.
+async fn check_encryption(room_id: &str, config: &Config) {
+	match room::is_encrypted(&config.matrix, room_id).await {
+		Ok(false) => {},
+		Ok(true) => {
+			warn!("room {room_id} is encrypted, pack links sent there can not be read");
+			if let Err(err) = room::send_message(&config.matrix, room_id, ENCRYPTED_NOTICE, None).await {
+				error!("{:?}", anyhow::Error::from(err).context("failed to send notice"));
+			}
+		},
+		Err(err) => error!(
+			"{:?}",
+			anyhow::Error::from(err).context(format!("failed to check the encryption of room {room_id}"))
+		)
+	}
+}
+
+async fn import_pack(
+	pack: &str,
+	room_id: &str,
+	opt: &Opt,
+	config: &Config,
+	database: Option<&dyn DynDatabase>
+) -> anyhow::Result<String> {
+	let tg_pack = tg::StickerPack::get(pack, &config.telegram)
+		.await
+		.with_context(|| format!("failed to get telegram sticker pack {pack:?}"))?;
+	let mut import_config = config.import_config(database, false);
+	import_config.repaint_color = opt.emoji_color;
+	import_config.premium_animations = opt.premium;
+	let import_config = import_config;
+
+	let (matrix_pack, errors) = match tg_pack.import(&config.telegram, &config.matrix, &import_config).await {
+		Ok(matrix_pack) => (matrix_pack, Vec::new()),
+		Err((matrix_pack, errors)) => {
+			for (index, err) in &errors {
+				error!("failed to import sticker {index} from pack {pack:?}: {err}");
+			}
+			(matrix_pack, errors)
+		}
+	};
+	let mut summary = if matrix_pack.stickers.is_empty() {
+		format!("Failed to import {pack}: no sticker could be imported")
+	} else {
+		let title = matrix_pack.title.clone();
+		let stickers = matrix_pack.stickers.len();
+		room::set_room_emotes(&config.matrix, room_id, tg_pack.name(), matrix_pack)
+			.await
+			.context("failed to set room emotes")?;
+		format!("Added {title:?} with {stickers} stickers as room emotes")
+	};
+	if !errors.is_empty() {
+		summary.push_str(&format!("\n{} stickers failed:", errors.len()));
+		for (index, err) in errors.iter().take(MAX_LISTED_ERRORS) {
+			summary.push_str(&format!("\n- sticker {index}: {err}"));
+		}
+		if errors.len() > MAX_LISTED_ERRORS {
+			summary.push_str("\n- …");
+		}
+	}
+	Ok(summary)
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let whoami = matrix::whoami(&config.matrix)
+		.await
+		.context("Error connecting to Matrix homeserver")?;
+	let database = config.database.open(&config.data_dir).await?;
+	let filter = json!({
+		"presence": { "types": [] },
+		"account_data": { "types": [] },
+		"room": {
+			"rooms": opt.rooms,
+			"state": { "types": [] },
+			"ephemeral": { "types": [] },
+			"account_data": { "types": [] },
+			"timeline": { "types": ["m.room.message", "m.room.encryption"] }
+		}
+	})
+	.to_string();
+
+	// skip the messages, which were sent before the bot was started
+	let mut since = room::sync(&config.matrix, None, 0, Some(&filter))
+		.await
+		.context("failed to sync")?
+		.next_batch()
+		.to_owned();
+	info!("{} is waiting for pack links", whoami.user_id());
+	loop {
+		let sync = match room::sync(&config.matrix, Some(&since), SYNC_TIMEOUT, Some(&filter)).await {
+			Ok(sync) => sync,
+			Err(err) => {
+				warn!("{:?}", anyhow::Error::from(err).context("failed to sync"));
+				sleep(RETRY_DELAY).await;
+				continue;
+			}
+		};
+		since = sync.next_batch().to_owned();
+		for room_id in sync.rooms().invite().keys() {
+			if !opt.rooms.contains(room_id) {
+				continue;
+			}
+			info!("join room {room_id}");
+			match room::join_room(&config.matrix, room_id).await {
+				// encrypted messages are not returned by the sync, so the bot would silently ignore all links
+				Ok(()) => check_encryption(room_id, &config).await,
+				Err(err) => error!(
+					"{:?}",
+					anyhow::Error::from(err).context(format!("failed to join room {room_id}"))
+				)
+			}
+		}
+		for (room_id, joined) in sync.rooms().join() {
+			if !opt.rooms.contains(room_id) {
+				continue;
+			}
+			for event in joined.timeline().events() {
+				if event.sender() == whoami.user_id() {
+					continue;
+				}
+				if event.event_type() == "m.room.encryption" {
+					check_encryption(room_id, &config).await;
+					continue;
+				}
+				// the links of an edited message were already imported, when the original message was sent
+				if event.is_edit() {
+					continue;
+				}
+				let Some(body) = event.body() else {
+					continue;
+				};
+				for pack in tg::PackRef::find_links(&strip_reply_fallback(body)) {
+					let pack = pack.name();
+					info!("{} requested pack {pack} at room {room_id}", event.sender());
+					let summary = match import_pack(pack, room_id, &opt, &config, database.as_deref()).await {
+						Ok(summary) => summary,
+						Err(err) => {
+							error!("{err:?}");
+							format!("Failed to import {pack}: {err:#}")
+						}
+					};
+					if let Err(err) = room::send_message(&config.matrix, room_id, &summary, Some(event.event_id())).await {
+						error!("{:?}", anyhow::Error::from(err).context("failed to answer message"));
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::strip_reply_fallback;
+
+	#[test]
+	fn reply_fallback() {
+		assert_eq!(
+			strip_reply_fallback("> <@alice:example.org> https://t.me/addstickers/Cats\n\nthanks"),
+			"thanks"
+		);
+		assert_eq!(
+			strip_reply_fallback(
+				"> <@alice:example.org> look at\n> https://t.me/addstickers/Cats\n> and t.me/addemoji/Dogs\n\nnice\n\nt.me/addstickers/Birds\n> not a quote"
+			),
+			"nice\n\nt.me/addstickers/Birds\n> not a quote"
+		);
+		assert_eq!(strip_reply_fallback("t.me/addstickers/Cats"), "t.me/addstickers/Cats");
+	}
+}
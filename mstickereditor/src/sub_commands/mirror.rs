@@ -0,0 +1,107 @@
+use crate::{
+	config::load_profile,
+	load_config_file,
+	pack_file::{parse_pack, replace_json_urls, to_upload, unique_images, write_atomic}
+};
+use anyhow::Context;
+use clap::Parser;
+use futures_util::stream::{self, StreamExt as _, TryStreamExt as _};
+use log::info;
+use mstickerlib::matrix::Mxc;
+use std::{collections::HashMap, path::PathBuf};
+use tokio::fs;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Maunium or ponies pack files to mirror
+	#[clap(required = true)]
+	packs: Vec<PathBuf>,
+
+	/// Profile of an account at the source homeserver, used to download the files.
+	/// By default the files are downloaded from the configured homeserver, which fetches them from the source server.
+	#[clap(long, value_name = "PROFILE")]
+	from_profile: Option<String>,
+
+	/// Directory to save the mirrored packs to, using the file names of the input packs
+	#[clap(short, long, value_name = "DIR", required_unless_present_any = &["in-place", "dryrun"])]
+	output_dir: Option<PathBuf>,
+
+	/// Overwrite the input pack files
+	#[clap(long, conflicts_with = "output-dir")]
+	in_place: bool,
+
+	/// Does only download the files, but does not upload them and does not save the packs
+	#[clap(short = 'd', long)]
+	dryrun: bool
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+	let source = match &opt.from_profile {
+		Some(profile) => load_profile(Some(profile))?.matrix,
+		None => load_config_file()?.matrix
+	};
+
+	config.check_matrix(opt.dryrun).await?;
+	let database = config.database.open(&config.data_dir).await?;
+	// urls used by multiple packs are only mirrored once
+	let mut new_urls: HashMap<String, Mxc> = HashMap::new();
+
+	if let Some(output_dir) = &opt.output_dir {
+		fs::create_dir_all(output_dir)
+			.await
+			.with_context(|| format!("failed to create dir {output_dir:?}"))?;
+	}
+
+	for path in opt.packs {
+		let mut json: serde_json::Value = serde_json::from_slice(&fs::read(&path).await?)
+			.with_context(|| format!("failed to read stickerpack {path:?}"))?;
+		let (_, pack) = parse_pack(json.clone()).with_context(|| format!("failed to read stickerpack {path:?}"))?;
+		let images: Vec<_> = unique_images(&pack)
+			.into_iter()
+			.filter(|image| !new_urls.contains_key(image.url.url()))
+			.cloned()
+			.collect();
+		info!("download {} files of pack {:?}", images.len(), pack.title);
+		let files: Vec<_> = stream::iter(images)
+			.map(|mut image| {
+				let source = &source;
+				async move {
+					let data = image
+						.url
+						.fetch_data(source)
+						.await
+						.with_context(|| format!("failed to download {}", image.url))?;
+					anyhow::Ok((image, data))
+				}
+			})
+			.buffer_unordered(8)
+			.try_collect()
+			.await?;
+		if opt.dryrun {
+			continue;
+		}
+
+		for (image, data) in files {
+			info!("upload {}", image.url);
+			let (new_mxc, has_uploaded) = to_upload(&image, data)?
+				.upload(&config.matrix, database.as_deref())
+				.await
+				.with_context(|| format!("failed to upload {}", image.url))?;
+			if !has_uploaded {
+				info!("  upload skipped; file with this hash was already uploaded");
+			}
+			new_urls.insert(image.url.url().to_owned(), new_mxc);
+		}
+		// only the urls are replaced, so fields unknown to mstickereditor are kept
+		replace_json_urls(&mut json, &new_urls);
+		let output = match &opt.output_dir {
+			Some(output_dir) => output_dir.join(path.file_name().context("pack path has no file name")?),
+			None => path
+		};
+		info!("save stickerpack to {:?}", output);
+		write_atomic(&output, &serde_json::to_vec(&json)?).await?;
+	}
+	Ok(())
+}
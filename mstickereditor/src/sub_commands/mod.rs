@@ -1,4 +1,19 @@
+pub mod bot;
+pub mod build_site;
+pub mod collect_room;
 pub mod create_index;
+pub mod doctor;
+pub mod export;
+pub mod export_telegram;
+pub mod export_whatsapp;
 pub mod import;
+pub mod import_archive;
+pub mod import_chat_export;
+pub mod import_dir;
+pub mod init;
+pub mod matrix_bot;
+pub mod mirror;
 pub mod print_shell_completion;
+pub mod serve;
 pub mod set_widget;
+pub mod update;
@@ -0,0 +1,185 @@
+use super::{build_site::PICKER_ASSETS, create_index::build_index};
+use crate::{load_config_file, matrix::set_widget};
+use anyhow::Context;
+use axum::{
+	extract::{Path, State},
+	http::{header, Method, StatusCode},
+	response::{IntoResponse, Response},
+	routing::get,
+	Router
+};
+use clap::Parser;
+use log::{error, info};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tokio::{fs, net::TcpListener};
+use tower_http::cors::{Any, CorsLayer};
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Address to listen on
+	#[clap(short, long, default_value = "127.0.0.1:8080")]
+	bind: SocketAddr,
+
+	/// Directory containing the stickerpacks
+	#[clap(short = 'd', long, value_name = "DIR", default_value = ".")]
+	packs_dir: PathBuf,
+
+	/// Matrix homeserver, with does render the preview thumbs
+	#[clap(short = 's', long)]
+	homeserver: Option<String>,
+
+	/// Enable the served sticker picker as widget for your matrix account.
+	/// Requires `--public-url`, since the bind address is usually not reachable by the matrix clients.
+	#[clap(long, requires = "public-url")]
+	set_widget: bool,
+
+	/// Url, which is used to reach the server from the matrix clients, like `https://stickers.example.org/`
+	#[clap(long, value_name = "URL")]
+	public_url: Option<String>
+}
+
+struct AppState {
+	packs_dir: PathBuf,
+	packs: Vec<String>,
+	index: String
+}
+
+fn content_type(file_name: &str) -> &'static str {
+	match file_name.rsplit('.').next() {
+		Some("html") => "text/html; charset=utf-8",
+		Some("js") => "text/javascript; charset=utf-8",
+		Some("css") => "text/css; charset=utf-8",
+		Some("json") => "application/json",
+		_ => "application/octet-stream"
+	}
+}
+
+async fn asset(file_name: Option<Path<String>>) -> Response {
+	let file_name = file_name.map(|Path(file_name)| file_name);
+	let file_name = file_name.as_deref().unwrap_or("index.html");
+	match PICKER_ASSETS.iter().find(|(name, _)| *name == file_name) {
+		Some((name, content)) => ([(header::CONTENT_TYPE, content_type(name))], *content).into_response(),
+		None => StatusCode::NOT_FOUND.into_response()
+	}
+}
+
+async fn pack(State(state): State<Arc<AppState>>, Path(file_name): Path<String>) -> Response {
+	if file_name == "index.json" {
+		return ([(header::CONTENT_TYPE, content_type(&file_name))], state.index.clone()).into_response();
+	}
+	// only serve packs, not any file of the directory
+	if !state.packs.contains(&file_name) {
+		return StatusCode::NOT_FOUND.into_response();
+	}
+	match fs::read(state.packs_dir.join(&file_name)).await {
+		Ok(data) => ([(header::CONTENT_TYPE, content_type(&file_name))], data).into_response(),
+		Err(err) => {
+			error!("failed to read stickerpack {file_name:?}: {err}");
+			StatusCode::INTERNAL_SERVER_ERROR.into_response()
+		}
+	}
+}
+
+fn app(state: AppState) -> Router {
+	// the widget is loaded as iframe from the matrix client
+	let cors = CorsLayer::new().allow_origin(Any).allow_methods([Method::GET]);
+	Router::new()
+		.route("/", get(asset))
+		.route("/{file_name}", get(asset))
+		.route("/packs/{file_name}", get(pack))
+		.layer(cors)
+		.with_state(Arc::new(state))
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let index = build_index(&opt.packs_dir, opt.homeserver)?;
+	let state = AppState {
+		packs_dir: opt.packs_dir,
+		index: serde_json::to_string(&index)?,
+		packs: index.packs
+	};
+	let app = app(state);
+
+	let listener = TcpListener::bind(opt.bind)
+		.await
+		.with_context(|| format!("failed to bind to {}", opt.bind))?;
+	info!("serve sticker picker at http://{}/", opt.bind);
+	if let Some(url) = opt.public_url.filter(|_| opt.set_widget) {
+		let config = load_config_file()?;
+		info!("enable sticker picker {url} as widget");
+		set_widget(&config.matrix, config.matrix.user.clone(), url)
+			.await
+			.context("Error enabling widget")?;
+	}
+	axum::serve(listener, app)
+		.with_graceful_shutdown(async {
+			tokio::signal::ctrl_c().await.ok();
+		})
+		.await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{app, AppState, Opt};
+	use axum::{
+		body::{to_bytes, Body},
+		http::{header, Request, StatusCode}
+	};
+	use clap::Parser;
+	use tower::ServiceExt as _;
+
+	/// status, content type and body of a request
+	async fn get(uri: &str) -> (StatusCode, String, String) {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("cats.json"), r#"{"title":"Cats"}"#).unwrap();
+		std::fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+		let state = AppState {
+			packs_dir: dir.path().to_owned(),
+			packs: vec!["cats.json".to_owned()],
+			index: r#"{"packs":["cats.json"],"homeserver_url":"https://example.org"}"#.to_owned()
+		};
+		let response = app(state)
+			.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+			.await
+			.unwrap();
+		let status = response.status();
+		let content_type = response
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.map(|value| value.to_str().unwrap().to_owned())
+			.unwrap_or_default();
+		let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+		(status, content_type, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	#[tokio::test]
+	async fn routes() {
+		let (status, content_type, body) = get("/packs/index.json").await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(content_type, "application/json");
+		assert!(body.contains("cats.json"));
+
+		let (status, _, body) = get("/packs/cats.json").await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body, r#"{"title":"Cats"}"#);
+		// only packs are served
+		assert_eq!(get("/packs/secret.txt").await.0, StatusCode::NOT_FOUND);
+
+		let (status, content_type, body) = get("/").await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(content_type.starts_with("text/html"));
+		assert!(body.contains("picker.js"));
+		let (status, content_type, _) = get("/picker.js").await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(content_type.starts_with("text/javascript"));
+		assert_eq!(get("/missing.js").await.0, StatusCode::NOT_FOUND);
+	}
+
+	#[test]
+	fn set_widget_requires_public_url() {
+		assert!(Opt::try_parse_from(["serve", "--set-widget"]).is_err());
+		assert!(Opt::try_parse_from(["serve", "--set-widget", "--public-url", "https://stickers.example.org/"]).is_ok());
+	}
+}
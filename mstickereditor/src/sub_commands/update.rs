@@ -0,0 +1,111 @@
+use super::import::parse_color;
+use crate::{
+	load_config_file,
+	pack_file::{parse_pack, update_json, write_atomic}
+};
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use log::{error, info, warn};
+use mstickerlib::{matrix, tg};
+use std::path::PathBuf;
+use tokio::fs;
+
+#[derive(Debug, Parser)]
+pub struct Opt {
+	/// Previously imported maunium or ponies pack files
+	#[clap(required = true)]
+	packs: Vec<PathBuf>,
+
+	/// Does not upload the sticker to Matrix and does not save the packs
+	#[clap(short = 'd', long)]
+	dryrun: bool,
+
+	/// Also import the full-screen effect of new premium stickers
+	#[clap(long)]
+	premium: bool,
+
+	/// Color of new custom emoji, which adapt to the text color at telegram, as hex code like `#ffffff`
+	#[clap(long, value_name = "COLOR", parse(try_from_str = parse_color))]
+	emoji_color: Option<[u8; 3]>
+}
+
+fn print_sticker(prefix: char, sticker: &matrix::sticker::Sticker) {
+	let index = sticker
+		.tg_sticker
+		.as_ref()
+		.and_then(|tg_sticker| tg_sticker.index)
+		.map(|index| format!("{index:03}"))
+		.unwrap_or_default();
+	println!("  {prefix} {index:>3} {:<2} {}", sticker.emoji.join(""), sticker.body);
+}
+
+#[tokio::main]
+pub async fn run(opt: Opt) -> anyhow::Result<()> {
+	let config = load_config_file()?;
+
+	config.check_matrix(opt.dryrun).await?;
+	let database = config.database.open(&config.data_dir).await?;
+	let mut import_config = config.import_config(database.as_deref(), opt.dryrun);
+	import_config.repaint_color = opt.emoji_color;
+	import_config.premium_animations = opt.premium;
+	let import_config = import_config;
+	let mut incomplete_packs = Vec::new();
+
+	for path in opt.packs {
+		let mut json: serde_json::Value = serde_json::from_slice(&fs::read(&path).await?)
+			.with_context(|| format!("failed to read stickerpack {path:?}"))?;
+		let (format, old_pack) = parse_pack(json.clone()).with_context(|| format!("failed to read stickerpack {path:?}"))?;
+		let name = old_pack
+			.tg_pack
+			.as_ref()
+			.map(|tg_pack| tg_pack.name.clone())
+			.or_else(|| old_pack.id.strip_prefix("tg_name_").map(ToOwned::to_owned))
+			.ok_or_else(|| anyhow!("stickerpack {path:?} was not imported from telegram"))?;
+		info!("loading data for {name}");
+		let tg_pack = tg::StickerPack::get(&name, &config.telegram)
+			.await
+			.with_context(|| format!("failed to get telegram sticker pack {name:?}"))?;
+		let (matrix_pack, diff) = match tg_pack
+			.update(old_pack, &config.telegram, &config.matrix, &import_config)
+			.await
+		{
+			Ok(value) => value,
+			Err((matrix_pack, diff, errors)) => {
+				for (index, err) in errors {
+					let err =
+						anyhow::Error::from(err).context(format!("failed to import sticker {index} from pack {name:?}"));
+					error!("{err:?}");
+				}
+				warn!("Sticker pack {name} is not complete");
+				incomplete_packs.push(name.clone());
+				(matrix_pack, diff)
+			}
+		};
+
+		println!(
+			"{}: {} added, {} removed, {} kept",
+			path.display(),
+			diff.added.len(),
+			diff.removed.len(),
+			diff.kept
+		);
+		for sticker in &diff.added {
+			print_sticker('+', sticker);
+		}
+		for sticker in &diff.removed {
+			print_sticker('-', sticker);
+		}
+
+		if opt.dryrun {
+			continue;
+		}
+		// only the changes are written, so values unknown to mstickereditor are kept
+		update_json(&mut json, format, matrix_pack)?;
+		info!("save stickerpack to {:?}", path);
+		write_atomic(&path, &serde_json::to_vec(&json)?).await?;
+	}
+	if !incomplete_packs.is_empty() {
+		bail!("The following packs are not complete {incomplete_packs:?}");
+	}
+	Ok(())
+}
@@ -1,10 +1,15 @@
 use anyhow;
+use futures_util::future::{FutureExt as _, LocalBoxFuture};
 use sha2::{Digest, Sha512};
 
 mod simple_file;
 pub use simple_file::FileDatabase;
 mod dummy_database;
 pub use dummy_database::DummyDatabase;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteDatabase;
 
 pub type Hash = [u8; 64];
 
@@ -15,6 +20,48 @@ pub trait Database {
 	async fn add(&self, hash: Hash, url: String) -> anyhow::Result<()>;
 }
 
+/// Object safe version of [Database], which allows choosing the database at runtime.
+///
+/// This trait is implemented for every [Database]
+/// and [Database] is implemented for `dyn DynDatabase`.
+/// So a `Box<dyn DynDatabase>` can be used everywhere, where a [Database] is expected:
+/// ```
+/// # use mstickerlib::{database::{DynDatabase, FileDatabase}, tg::ImportConfig};
+/// # async fn foo() -> std::io::Result<()> {
+/// let database: Box<dyn DynDatabase> = Box::new(FileDatabase::new("uploads").await?);
+/// let mut import_config = ImportConfig::<dyn DynDatabase>::default();
+/// import_config.database = Some(&*database);
+/// # Ok(())
+/// # }
+/// ```
+pub trait DynDatabase {
+	fn get_boxed<'a>(&'a self, hash: &'a Hash) -> LocalBoxFuture<'a, anyhow::Result<Option<String>>>;
+	fn add_boxed(&self, hash: Hash, url: String) -> LocalBoxFuture<'_, anyhow::Result<()>>;
+}
+
+impl<D> DynDatabase for D
+where
+	D: Database
+{
+	fn get_boxed<'a>(&'a self, hash: &'a Hash) -> LocalBoxFuture<'a, anyhow::Result<Option<String>>> {
+		self.get(hash).boxed_local()
+	}
+
+	fn add_boxed(&self, hash: Hash, url: String) -> LocalBoxFuture<'_, anyhow::Result<()>> {
+		self.add(hash, url).boxed_local()
+	}
+}
+
+impl Database for dyn DynDatabase + '_ {
+	async fn get(&self, hash: &Hash) -> anyhow::Result<Option<String>> {
+		self.get_boxed(hash).await
+	}
+
+	async fn add(&self, hash: Hash, url: String) -> anyhow::Result<()> {
+		self.add_boxed(hash, url).await
+	}
+}
+
 pub fn hash(value: &[u8]) -> Hash {
 	let mut hasher = Sha512::new();
 	hasher.update(value);
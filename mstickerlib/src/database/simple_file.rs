@@ -5,7 +5,12 @@ use futures_util::stream::StreamExt as _;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
 use serde_json;
-use std::{collections::BTreeMap, io, path::Path};
+use std::{
+	collections::BTreeMap,
+	ffi::OsString,
+	io,
+	path::{Path, PathBuf}
+};
 use tokio::{
 	fs::{self, File},
 	io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
@@ -13,6 +18,13 @@ use tokio::{
 };
 use tokio_stream::wrappers::LinesStream;
 
+#[cfg(feature = "log")]
+use log::{info, warn};
+
+/// Compact the database file at opening, if it contains more than this many lines,
+/// which does not contribute to the current state (duplicates and unreadable lines).
+const COMPACT_THRESHOLD: usize = 256;
+
 #[derive(Debug, Deserialize, Serialize)]
 struct HashUrl {
 	#[serde(with = "BigArray")]
@@ -22,44 +34,131 @@ struct HashUrl {
 
 /// simple implemtation of the `Database` traid,
 /// with does save data to a file
+///
+/// The database holds an exclusive advisory lock on `<path>.lock` as long as it exists.
+/// So a second process opening the same file will wait, until the first one has dropped its database.
 pub struct FileDatabase {
 	tree: RwLock<BTreeMap<Hash, String>>,
-	file: Mutex<fs::File>
+	file: Mutex<fs::File>,
+	/// line numbers of the database file, which could not be read at opening
+	skipped_lines: Vec<usize>,
+	/// keep the lock file open, the lock is released when the file is closed
+	_lock: std::fs::File
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+	let mut file_name: OsString = path.file_name().unwrap_or_default().to_owned();
+	file_name.push(suffix);
+	path.with_file_name(file_name)
+}
+
+/// Lock the database. If it is used by another process,
+/// wait until it is released or fail with [io::ErrorKind::WouldBlock], if `wait` is false.
+async fn lock(path: &Path, wait: bool) -> io::Result<std::fs::File> {
+	let lock_file = std::fs::OpenOptions::new()
+		.write(true)
+		.create(true)
+		.truncate(false)
+		.open(sibling_path(path, ".lock"))?;
+	match lock_file.try_lock() {
+		Ok(()) => return Ok(lock_file),
+		Err(std::fs::TryLockError::Error(error)) => return Err(error),
+		Err(std::fs::TryLockError::WouldBlock) => {}
+	}
+	if !wait {
+		return Err(io::Error::new(
+			io::ErrorKind::WouldBlock,
+			format!("Database({}) is used by another process", path.display())
+		));
+	}
+	#[cfg(feature = "log")]
+	info!(
+		"Database({}) is used by another process, waiting for it to finish",
+		path.display()
+	);
+	tokio::task::spawn_blocking(move || lock_file.lock().map(|_| lock_file)).await?
+}
+
+/// write all entries to a new file and replace the old one with it,
+/// so the database file is never left in a half written state.
+async fn compact(path: &Path, tree: &BTreeMap<Hash, String>) -> io::Result<()> {
+	let tmp_path = sibling_path(path, ".tmp");
+	let mut data = Vec::new();
+	for (hash, url) in tree {
+		serde_json::to_writer(&mut data, &HashUrl {
+			hash: *hash,
+			url: url.to_owned()
+		})?;
+		data.push(b'\n');
+	}
+	let mut tmp = File::create(&tmp_path).await?;
+	tmp.write_all(&data).await?;
+	tmp.sync_all().await?;
+	drop(tmp);
+	fs::rename(&tmp_path, path).await
 }
 
 impl FileDatabase {
+	/// Open the database. If it is used by another process, wait until it is released.
 	pub async fn new<P>(path: P) -> io::Result<FileDatabase>
 	where
 		P: AsRef<Path>
 	{
-		let path = path.as_ref();
+		Self::open(path.as_ref(), true).await
+	}
+
+	/// Open the database without waiting.
+	/// Fail with [io::ErrorKind::WouldBlock], if it is used by another process.
+	pub async fn try_new<P>(path: P) -> io::Result<FileDatabase>
+	where
+		P: AsRef<Path>
+	{
+		Self::open(path.as_ref(), false).await
+	}
+
+	async fn open(path: &Path, wait: bool) -> io::Result<FileDatabase> {
+		let lock = lock(path, wait).await?;
 		let mut tree = BTreeMap::<Hash, String>::new();
+		let mut lines_count = 0;
+		let mut skipped_lines = Vec::new();
 		match File::open(path).await {
 			Ok(file) => {
 				let bufreader = BufReader::new(file);
 				let mut lines = LinesStream::new(bufreader.lines()).enumerate();
 				while let Some((i, line)) = lines.next().await {
+					lines_count += 1;
 					let hashurl: Result<HashUrl, serde_json::Error> = serde_json::from_str(&line?);
 					match hashurl {
 						Ok(value) => {
 							tree.insert(value.hash, value.url);
 						},
-						Err(error) => eprintln!(
-							"Warning: Line {} of Database({}) can not be read: {:?}",
-							i + 1,
-							path.display(),
-							error
-						)
+						Err(error) => {
+							#[cfg(feature = "log")]
+							warn!("Line {} of Database({}) can not be read: {:?}", i + 1, path.display(), error);
+							#[cfg(not(feature = "log"))]
+							let _ = error; //fix unused warning
+							skipped_lines.push(i + 1);
+						}
 					};
 				}
 			},
 			Err(error) if error.kind() == io::ErrorKind::NotFound => {
-				print!("database not found, creating a new one");
+				#[cfg(feature = "log")]
+				info!("Database({}) not found, creating a new one", path.display());
 			},
 			Err(error) => {
 				return Err(error);
 			}
 		};
+		let redundant_lines = lines_count - tree.len();
+		if redundant_lines > COMPACT_THRESHOLD {
+			#[cfg(feature = "log")]
+			info!(
+				"compact Database({}), dropping {redundant_lines} duplicate or unreadable lines",
+				path.display()
+			);
+			compact(path, &tree).await?;
+		}
 		let file = fs::OpenOptions::new()
 			.write(true)
 			.append(true)
@@ -68,9 +167,17 @@ impl FileDatabase {
 			.await?;
 		Ok(FileDatabase {
 			tree: RwLock::new(tree),
-			file: Mutex::new(file)
+			file: Mutex::new(file),
+			skipped_lines,
+			_lock: lock
 		})
 	}
+
+	/// Line numbers (starting at 1) of the database file, which could not be read and were skipped at opening.
+	/// The skipped lines are removed from the file, if the database is compacted.
+	pub fn skipped_lines(&self) -> &[usize] {
+		&self.skipped_lines
+	}
 }
 
 impl Database for FileDatabase {
@@ -83,9 +190,12 @@ impl Database for FileDatabase {
 	async fn add(&self, hash: Hash, url: String) -> anyhow::Result<()> {
 		let hash_url = HashUrl { hash, url };
 
+		// write the whole line at once, so a crash can not leave a half line behind
+		let mut line = serde_json::to_vec(&hash_url)?;
+		line.push(b'\n');
 		let mut file = self.file.lock().await;
-		file.write_all(&serde_json::to_vec(&hash_url)?).await?;
-		file.write_all(b"\n").await?;
+		file.write_all(&line).await?;
+		file.flush().await?;
 		drop(file);
 
 		let mut tree = self.tree.write().await;
@@ -93,3 +203,67 @@ impl Database for FileDatabase {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{FileDatabase, COMPACT_THRESHOLD};
+	use crate::database::{hash, Database};
+
+	#[tokio::test]
+	async fn compact_duplicates() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("uploads");
+		let database = FileDatabase::new(&path).await.unwrap();
+		for i in 0..COMPACT_THRESHOLD + 2 {
+			database
+				.add(hash(b"sticker"), format!("mxc://example.org/{i}"))
+				.await
+				.unwrap();
+		}
+		database
+			.add(hash(b"other"), "mxc://example.org/other".to_owned())
+			.await
+			.unwrap();
+		drop(database);
+		let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+		assert_eq!(lines, COMPACT_THRESHOLD + 3);
+
+		let database = FileDatabase::new(&path).await.unwrap();
+		let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+		assert_eq!(lines, 2);
+		assert_eq!(
+			database.get(&hash(b"sticker")).await.unwrap().as_deref(),
+			Some(format!("mxc://example.org/{}", COMPACT_THRESHOLD + 1).as_str())
+		);
+	}
+
+	#[tokio::test]
+	async fn try_new_locked() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("uploads");
+		let database = FileDatabase::new(&path).await.unwrap();
+		let err = FileDatabase::try_new(&path).await.err().unwrap();
+		assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+		drop(database);
+		FileDatabase::try_new(&path).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn skip_corrupt_lines() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("uploads");
+		let database = FileDatabase::new(&path).await.unwrap();
+		database
+			.add(hash(b"sticker"), "mxc://example.org/sticker".to_owned())
+			.await
+			.unwrap();
+		drop(database);
+		let mut data = std::fs::read_to_string(&path).unwrap();
+		data.push_str("{\"hash\": [1, 2\n");
+		std::fs::write(&path, data).unwrap();
+
+		let database = FileDatabase::new(&path).await.unwrap();
+		assert_eq!(database.skipped_lines(), [2]);
+		assert!(database.get(&hash(b"sticker")).await.unwrap().is_some());
+	}
+}
@@ -0,0 +1,112 @@
+use super::{Database, Hash};
+
+use anyhow;
+use rusqlite::{Connection, OptionalExtension as _};
+use std::{
+	path::Path,
+	sync::{Arc, Mutex, PoisonError},
+	time::Duration
+};
+
+/// How long to wait for another process holding a lock at the database, before failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// implemtation of the `Database` traid, with does save data to a sqlite database.
+///
+/// Sqlite does take care of locking by itself,
+/// so the same database can be used by multiple processes at the same time.
+/// The database uses write-ahead logging, so readers do not block each other nor the writer.
+pub struct SqliteDatabase {
+	connection: Arc<Mutex<Connection>>
+}
+
+impl SqliteDatabase {
+	pub fn new<P>(path: P) -> rusqlite::Result<SqliteDatabase>
+	where
+		P: AsRef<Path>
+	{
+		let connection = Connection::open(path)?;
+		connection.busy_timeout(BUSY_TIMEOUT)?;
+		connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+		connection.execute(
+			"CREATE TABLE IF NOT EXISTS uploads (hash BLOB PRIMARY KEY NOT NULL, url TEXT NOT NULL)",
+			()
+		)?;
+		Ok(SqliteDatabase {
+			connection: Arc::new(Mutex::new(connection))
+		})
+	}
+
+	/// run a query at a blocking thread, so waiting for sqlite does not block the async runtime
+	async fn run<F, T>(&self, query: F) -> anyhow::Result<T>
+	where
+		F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+		T: Send + 'static
+	{
+		let connection = self.connection.clone();
+		let result = tokio::task::spawn_blocking(move || {
+			let connection = connection.lock().unwrap_or_else(PoisonError::into_inner);
+			query(&connection)
+		})
+		.await?;
+		Ok(result?)
+	}
+}
+
+impl Database for SqliteDatabase {
+	async fn get(&self, hash: &Hash) -> anyhow::Result<Option<String>> {
+		let hash = *hash;
+		self.run(move |connection| {
+			connection
+				.query_row("SELECT url FROM uploads WHERE hash = ?1", [&hash[..]], |row| row.get(0))
+				.optional()
+		})
+		.await
+	}
+
+	async fn add(&self, hash: Hash, url: String) -> anyhow::Result<()> {
+		self.run(move |connection| {
+			connection.execute("INSERT OR REPLACE INTO uploads (hash, url) VALUES (?1, ?2)", (&hash[..], url))?;
+			Ok(())
+		})
+		.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::SqliteDatabase;
+	use crate::database::{hash, Database};
+
+	#[tokio::test]
+	async fn add_and_get() {
+		let dir = tempfile::tempdir().unwrap();
+		let database = SqliteDatabase::new(dir.path().join("uploads.sqlite")).unwrap();
+		assert_eq!(database.get(&hash(b"sticker")).await.unwrap(), None);
+		database
+			.add(hash(b"sticker"), "mxc://example.org/a".to_owned())
+			.await
+			.unwrap();
+		database
+			.add(hash(b"sticker"), "mxc://example.org/b".to_owned())
+			.await
+			.unwrap();
+		assert_eq!(
+			database.get(&hash(b"sticker")).await.unwrap().as_deref(),
+			Some("mxc://example.org/b")
+		);
+	}
+
+	#[tokio::test]
+	async fn multiple_connections() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("uploads.sqlite");
+		let first = SqliteDatabase::new(&path).unwrap();
+		let second = SqliteDatabase::new(&path).unwrap();
+		first.add(hash(b"sticker"), "mxc://example.org/a".to_owned()).await.unwrap();
+		assert_eq!(
+			second.get(&hash(b"sticker")).await.unwrap().as_deref(),
+			Some("mxc://example.org/a")
+		);
+	}
+}
@@ -1,11 +1,11 @@
 pub use crate::matrix::MatrixApiError;
 use reqwest::StatusCode;
-use std::{fmt::Display, io};
+use std::{fmt::Display, io, path::PathBuf};
 use thiserror::Error;
 use tokio::task::JoinError;
 
 #[derive(Error, Debug)]
-#[error("{0:?} does not look like a Telegram StickerPack\nPack url should start with \"https://t.me/addstickers/\", \"t.me/addstickers/\" or \"tg://addstickers?set=\"")]
+#[error("{0:?} does not look like a Telegram StickerPack\nPack url should look like \"https://t.me/addstickers/<name>\", \"tg://addstickers?set=<name>\" or \"https://t.me/addemoji/<name>\" for custom emoji; or be the name of the pack")]
 pub struct InvalidPackUrl(pub String);
 
 #[derive(Error, Debug)]
@@ -19,6 +19,14 @@ pub struct TelgramApiError {
 #[error("no extension/mimetyp found at sticker filename")]
 pub struct NoMimeType;
 
+#[derive(Error, Debug)]
+#[error("{0:?} is not a valid mxc url")]
+pub struct InvalidMxcUrl(pub String);
+
+#[derive(Error, Debug)]
+#[error("format of {0:?} is not supported or could not be detected")]
+pub struct UnknownFormat(pub PathBuf);
+
 #[derive(Error, Debug)]
 pub struct MatrixError {
 	pub status_code: StatusCode,
@@ -52,7 +60,10 @@ pub enum UnsupportedFormat {
 	#[cfg(not(feature = "lottie"))]
 	Lottie,
 	#[cfg(not(feature = "ffmpeg"))]
-	Webm
+	Webm,
+	/// rescaling or re-encoding of images
+	#[cfg(not(feature = "ffmpeg"))]
+	Conversion
 }
 
 #[cfg(any(not(feature = "ffmpeg"), not(feature = "lottie")))]
@@ -60,16 +71,18 @@ impl Display for UnsupportedFormat {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			#[cfg(not(feature = "lottie"))]
-			Self::Lottie => write!(f, "animated")?,
+			Self::Lottie => write!(f, "animated sticker are")?,
+			#[cfg(not(feature = "ffmpeg"))]
+			Self::Webm => write!(f, "video sticker are")?,
 			#[cfg(not(feature = "ffmpeg"))]
-			Self::Webm => write!(f, "video")?
+			Self::Conversion => write!(f, "converting images is")?
 		}
-		write!(f, " sticker are unsupported, since mstickerlib was compliled without the ",)?;
+		write!(f, " unsupported, since mstickerlib was compliled without the ",)?;
 		match self {
 			#[cfg(not(feature = "lottie"))]
 			Self::Lottie => write!(f, "\"lottie\"")?,
 			#[cfg(not(feature = "ffmpeg"))]
-			Self::Webm => write!(f, "\"ffmpeg\"")?
+			Self::Webm | Self::Conversion => write!(f, "\"ffmpeg\"")?
 		}
 		write!(f, " feature.")
 	}
@@ -106,6 +119,13 @@ pub enum Error {
 	Webp(#[from] webp_animation::Error),
 	#[error(transparent)]
 	NoMimeType(#[from] NoMimeType),
+	/// `getFile` has not returned a path to download the file
+	#[error("telegram has not returned a download path for file {0:?}")]
+	NoFilePath(String),
+	#[error(transparent)]
+	UnknownFormat(#[from] UnknownFormat),
+	#[error(transparent)]
+	InvalidMxcUrl(#[from] InvalidMxcUrl),
 	/// to avoid that this struct is generic for the database error use anyhow
 	/// This is the error crated by the user choosen databe trait impl at the import function function
 	#[error("failed to insert or check for file duplicate at the database: {0:?}")]
@@ -116,5 +136,12 @@ pub enum Error {
 	#[error(transparent)]
 	UnsupportedFormat(#[from] UnsupportedFormat),
 	#[error("Invalid matrix homeserver urls: {0}")]
-	InvalidHomeServerUrl(#[from] url::ParseError)
+	InvalidHomeServerUrl(#[from] url::ParseError),
+	/// the sticker could not be compressed below the size limit
+	#[error("sticker {file_name:?} can not be compressed to {max_size} bytes")]
+	TooLarge { file_name: String, max_size: usize },
+	#[error("failed to serialize json: {0}")]
+	Json(#[from] serde_json::Error),
+	#[error("failed to write zip archive: {0}")]
+	Zip(#[from] zip::result::ZipError)
 }
@@ -1,21 +1,27 @@
 #[cfg(feature = "ffmpeg")]
-use crate::video::webm2webp;
+use crate::video::{self, webm2webp};
 use crate::{
 	database,
-	error::{Error, NoMimeType},
-	matrix::{self, Config, Mxc}
+	error::{Error, NoMimeType, UnknownFormat},
+	matrix::{self, sticker_formats::ponies, Config, Mxc},
+	tg::{ImportConfig, StickerFormat}
 };
 #[cfg(feature = "lottie")]
 use lottieconv::{Animation, Converter, Rgba};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
-#[cfg(any(feature = "ffmpeg", feature = "lottie"))]
-use std::io::Write;
-use std::{io::Read, path::Path, sync::Arc};
+use std::{
+	io::{self, Read, Write as _},
+	path::Path,
+	sync::Arc
+};
 use strum_macros::Display;
 #[cfg(feature = "lottie")]
 use tempfile::NamedTempFile;
 
+#[cfg(feature = "log")]
+use log::warn;
+
 // todo: remove copy trait. Or will gif support droppet first?
 #[derive(Clone, Copy, Debug, Default, Deserialize, Display)]
 #[serde(tag = "animation_format", rename_all = "lowercase")]
@@ -28,6 +34,7 @@ pub enum AnimationFormat {
 }
 
 /// Generic image struct, containing the image data and its meta data.
+#[derive(Clone)]
 pub struct Image {
 	pub file_name: String,
 	pub data: Arc<Vec<u8>>,
@@ -35,6 +42,128 @@ pub struct Image {
 	pub height: u32
 }
 
+/// size of a lottie animation
+#[derive(Deserialize)]
+struct LottieSize {
+	w: f64,
+	h: f64
+}
+
+/// read an EBML variable size integer; return the value including the length marker and its length
+fn ebml_vint(data: &[u8], max_len: u32) -> Option<(u64, usize)> {
+	let len = data.first()?.leading_zeros() + 1;
+	if len > max_len || data.len() < len as usize {
+		return None;
+	}
+	let value = data[..len as usize].iter().fold(0, |value, byte| (value << 8) | *byte as u64);
+	Some((value, len as usize))
+}
+
+/// get the size of the first video track of a webm file
+fn webm_size(data: &[u8]) -> Option<(u32, u32)> {
+	const SEGMENT: u64 = 0x1853_8067;
+	const TRACKS: u64 = 0x1654_AE6B;
+	const TRACK_ENTRY: u64 = 0xAE;
+	const VIDEO: u64 = 0xE0;
+	const PIXEL_WIDTH: u64 = 0xB0;
+	const PIXEL_HEIGHT: u64 = 0xBA;
+
+	let mut cursor = data;
+	let (mut width, mut height) = (None, None);
+	while width.is_none() || height.is_none() {
+		let (id, id_len) = ebml_vint(cursor, 4)?;
+		let (size, size_len) = ebml_vint(&cursor[id_len..], 8)?;
+		let rest = &cursor[id_len + size_len..];
+		// remove length marker; if all other bits are set the size is unknown
+		let marker = 1 << (7 * size_len);
+		let size = match size ^ marker {
+			size if size == marker - 1 => rest.len(),
+			size => (size as usize).min(rest.len())
+		};
+		let (body, next) = rest.split_at(size);
+		let uint = || body.iter().fold(0, |value, byte| (value << 8) | *byte as u32);
+		match id {
+			SEGMENT | TRACKS | TRACK_ENTRY | VIDEO => cursor = body,
+			PIXEL_WIDTH => (width, cursor) = (Some(uint()), next),
+			PIXEL_HEIGHT => (height, cursor) = (Some(uint()), next),
+			_ => cursor = next
+		}
+	}
+	Some((width?, height?))
+}
+
+/// detect the format of an image by its content; return the file extension and the size of the image
+pub fn sniff_format(data: &[u8]) -> Option<(&'static str, u32, u32)> {
+	let u16_le = |i: usize| Some(u16::from_le_bytes(data.get(i..i + 2)?.try_into().unwrap()) as u32);
+	let u24_le = |i: usize| Some(u32::from_le_bytes([*data.get(i)?, *data.get(i + 1)?, *data.get(i + 2)?, 0]));
+	let u32_le = |i: usize| Some(u32::from_le_bytes(data.get(i..i + 4)?.try_into().unwrap()));
+	let u32_be = |i: usize| Some(u32::from_be_bytes(data.get(i..i + 4)?.try_into().unwrap()));
+
+	if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+		return Some(("png", u32_be(16)?, u32_be(20)?));
+	}
+	if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+		return Some(("gif", u16_le(6)?, u16_le(8)?));
+	}
+	if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+		let (width, height) = match data.get(12..16)? {
+			b"VP8 " => (u16_le(26)? & 0x3fff, u16_le(28)? & 0x3fff),
+			b"VP8L" => {
+				let bits = u32_le(21)?;
+				((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1)
+			},
+			b"VP8X" => (u24_le(24)? + 1, u24_le(27)? + 1),
+			_ => return None
+		};
+		return Some(("webp", width, height));
+	}
+	if data.starts_with(&[0x1a, 0x45, 0xdf, 0xa3]) {
+		let (width, height) = webm_size(data)?;
+		return Some(("webm", width, height));
+	}
+	if data.starts_with(&[0x1f, 0x8b]) {
+		let mut json = Vec::new();
+		flate2::read::GzDecoder::new(data).read_to_end(&mut json).ok()?;
+		let size: LottieSize = serde_json::from_slice(&json).ok()?;
+		return Some(("tgs", size.w as u32, size.h as u32));
+	}
+	if data.first() == Some(&b'{') {
+		let size: LottieSize = serde_json::from_slice(data).ok()?;
+		return Some(("lottie", size.w as u32, size.h as u32));
+	}
+	None
+}
+
+/// Replace all colors of a lottie animation by `color`.
+/// Colors are stored as `[r, g, b, a]` with values between 0 and 1 at the `k` value of the `c` property of fills and strokes.
+fn repaint_lottie(value: &mut serde_json::Value, color: [u8; 3]) {
+	let set_color = |values: &mut Vec<serde_json::Value>| {
+		if values.len() >= 3 && values.iter().all(|value| value.is_number()) {
+			for (value, channel) in values.iter_mut().zip(color) {
+				*value = (channel as f64 / 255.0).into();
+			}
+		}
+	};
+	match value {
+		serde_json::Value::Object(map) => {
+			if let Some(serde_json::Value::Array(keyframes)) = map.get_mut("c").and_then(|c| c.get_mut("k")) {
+				set_color(keyframes);
+				// animated colors
+				for keyframe in keyframes.iter_mut() {
+					for key in ["s", "e"] {
+						if let Some(serde_json::Value::Array(values)) = keyframe.get_mut(key) {
+							set_color(values);
+						}
+					}
+				}
+			}
+			map.values_mut().for_each(|value| repaint_lottie(value, color));
+		},
+		serde_json::Value::Array(values) => values.iter_mut().for_each(|value| repaint_lottie(value, color)),
+		_ => {}
+	}
+}
+
 fn rayon_run<F, T>(callback: F) -> T
 where
 	F: FnOnce() -> T + Send,
@@ -49,6 +178,26 @@ where
 }
 
 impl Image {
+	/// Load an image from a file.
+	///
+	/// The format and size are detected by the content of the file, not by its extension.
+	/// Supported are png, gif, webp, webm, tgs and lottie (json) files.
+	pub async fn from_file<P>(path: P) -> Result<Self, Error>
+	where
+		P: AsRef<Path>
+	{
+		let path = path.as_ref();
+		let data = tokio::fs::read(path).await?;
+		let (extension, width, height) = sniff_format(&data).ok_or_else(|| UnknownFormat(path.to_owned()))?;
+		let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("sticker");
+		Ok(Self {
+			file_name: format!("{stem}.{extension}"),
+			data: Arc::new(data),
+			width,
+			height
+		})
+	}
+
 	pub fn mime_type(&self) -> Result<String, NoMimeType> {
 		let extension = Path::new(&self.file_name)
 			.extension()
@@ -125,6 +274,9 @@ impl Image {
 		if !self.file_name.ends_with(".webm") {
 			return Ok(self);
 		}
+		// ffmpeg is normally initialized together with the http client,
+		// but the image can also be converted without performing any request before.
+		crate::init();
 
 		tokio::task::spawn_blocking(move || {
 			rayon_run(move || {
@@ -145,18 +297,331 @@ impl Image {
 		.await?
 	}
 
+	/// Convert animated and video stickers to a format supported by matrix clients, like it is configured at `advance_config`.
+	pub async fn convert<D>(mut self, advance_config: &ImportConfig<'_, D>) -> Result<Self, Error>
+	where
+		D: database::Database + ?Sized
+	{
+		self = self.unpack_tgs().await?;
+		if self.file_name.ends_with(".lottie") && !advance_config.keep_lottie {
+			// file extension is now checked double.
+			// Here and inside `convert_...`
+			// But `convert_...` function does not exist, if feature is dissable.
+			#[cfg(feature = "lottie")]
+			{
+				self = self.convert_lottie(advance_config.animation_format).await?;
+			}
+			#[cfg(not(feature = "lottie"))]
+			return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Lottie));
+		}
+		if self.file_name.ends_with(".webm") && !advance_config.keep_webm {
+			#[cfg(feature = "ffmpeg")]
+			{
+				self = self.convert_webm2webp().await?;
+			}
+			#[cfg(not(feature = "ffmpeg"))]
+			return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Webm));
+		}
+		Ok(self)
+	}
+
+	/// Replace the color of all pixels by `color`, keeping the transparency.
+	/// Telegram does this for custom emoji, which adapt to the text color.
+	///
+	/// Only lottie and webp images can be repainted, other formats are returned unchanged.
+	pub async fn repaint(self, color: [u8; 3]) -> Result<Self, Error> {
+		if self.file_name.ends_with(".lottie") {
+			let mut lottie: serde_json::Value = serde_json::from_slice(&self.data).map_err(io::Error::from)?;
+			repaint_lottie(&mut lottie, color);
+			return Ok(Self {
+				data: Arc::new(serde_json::to_vec(&lottie).map_err(io::Error::from)?),
+				..self
+			});
+		}
+		#[cfg(any(feature = "ffmpeg", feature = "lottie"))]
+		if self.file_name.ends_with(".webp") {
+			return tokio::task::spawn_blocking(move || {
+				rayon_run(move || {
+					let decoder = webp_animation::Decoder::new(&self.data)?;
+					let mut encoder = webp_animation::Encoder::new(decoder.dimensions())?;
+					// the timestamp of a webp frame is the time when the frame ends
+					let mut timestamp = 0;
+					for frame in decoder {
+						let mut data = frame.data().to_vec();
+						for pixel in data.chunks_exact_mut(4) {
+							pixel[..3].copy_from_slice(&color);
+						}
+						encoder.add_frame(&data, timestamp)?;
+						timestamp = frame.timestamp();
+					}
+					let webp = encoder.finalize(timestamp.max(1))?;
+					Ok(Self {
+						data: Arc::new(webp.to_vec()),
+						..self
+					})
+				})
+			})
+			.await?;
+		}
+		#[cfg(feature = "log")]
+		warn!("  {} can not be repainted, keep original color", self.file_name);
+		Ok(self)
+	}
+
+	/// Convert the image to a format accepted by telegram for stickers.
+	///
+	/// Static images are converted to png and animations to vp9 webm videos, with 512 pixels at the longer side.
+	/// Videos are cut after 3 seconds and their bit rate is lowered until they fit into 256 KB.
+	/// png and webp images with the right size are not converted and lottie files are compressed to tgs,
+	/// all other formats require the `ffmpeg` feature.
+	pub async fn convert_for_telegram(self) -> Result<(Self, StickerFormat), Error> {
+		let (extension, width, height) =
+			sniff_format(&self.data).ok_or_else(|| UnknownFormat(self.file_name.clone().into()))?;
+		let stem = Path::new(&self.file_name)
+			.file_stem()
+			.and_then(|stem| stem.to_str())
+			.unwrap_or("sticker")
+			.to_owned();
+		// flag for animation at the VP8X chunk
+		let animated_webp =
+			self.data.get(12..16) == Some(b"VP8X") && self.data.get(20).is_some_and(|flags| flags & 0x02 != 0);
+		match extension {
+			"tgs" => return Ok((self, StickerFormat::Animated)),
+			"lottie" => {
+				let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+				encoder.write_all(&self.data)?;
+				let image = Self {
+					file_name: format!("{stem}.tgs"),
+					data: Arc::new(encoder.finish()?),
+					width,
+					height
+				};
+				return Ok((image, StickerFormat::Animated));
+			},
+			"png" | "webp" if !animated_webp && width.max(height) == 512 && self.data.len() <= 512 * 1024 => {
+				return Ok((
+					Self {
+						file_name: format!("{stem}.{extension}"),
+						width,
+						height,
+						..self
+					},
+					StickerFormat::Static
+				));
+			},
+			_ => {}
+		}
+
+		#[cfg(not(feature = "ffmpeg"))]
+		return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Conversion));
+		#[cfg(feature = "ffmpeg")]
+		{
+			crate::init();
+			let size = if width >= height {
+				(512, (height * 512 / width.max(1)).max(1))
+			} else {
+				((width * 512 / height).max(1), 512)
+			};
+			tokio::task::spawn_blocking(move || {
+				rayon_run(move || {
+					let frames = if extension == "webp" {
+						video::decode_webp(&self.data)?
+					} else {
+						let mut tmp = tempfile::Builder::new().suffix(&format!(".{extension}")).tempfile()?;
+						tmp.write_all(&self.data)?;
+						tmp.flush()?;
+						video::decode_frames(&tmp.path())?
+					};
+					let (file_name, data, format) = if frames.frames.len() > 1 {
+						if frames.frames.last().is_some_and(|(_, timestamp)| *timestamp >= 3000) {
+							#[cfg(feature = "log")]
+							warn!("  {} is longer than 3 seconds and will be cut", self.file_name);
+						}
+						// telegram allows at most 256 KB for video stickers
+						let max_size = 256 * 1024;
+						let webm =
+							video::encode_vp9_max_size(&frames, size, 3000, max_size)?.ok_or_else(|| Error::TooLarge {
+								file_name: self.file_name.clone(),
+								max_size
+							})?;
+						(format!("{stem}.webm"), webm, StickerFormat::Video)
+					} else {
+						(
+							format!("{stem}.png"),
+							video::encode_png(&frames, size)?,
+							StickerFormat::Static
+						)
+					};
+					Ok((
+						Self {
+							file_name,
+							data: Arc::new(data),
+							width: size.0,
+							height: size.1
+						},
+						format
+					))
+				})
+			})
+			.await?
+		}
+	}
+
+	/// Decode all frames of the image for re-encoding.
+	/// Animated telegram stickers are rendered first, which requires the `lottie` feature.
+	#[cfg(feature = "ffmpeg")]
+	async fn decode_frames(self) -> Result<video::Frames, Error> {
+		let (extension, ..) = sniff_format(&self.data).ok_or_else(|| UnknownFormat(self.file_name.clone().into()))?;
+		let image = match extension {
+			#[cfg(feature = "lottie")]
+			"tgs" | "lottie" => {
+				Self {
+					file_name: format!("sticker.{extension}"),
+					..self
+				}
+				.unpack_tgs()
+				.await?
+				.convert_lottie(AnimationFormat::Webp)
+				.await?
+			},
+			#[cfg(not(feature = "lottie"))]
+			"tgs" | "lottie" => return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Lottie)),
+			_ => self
+		};
+		let (extension, ..) = sniff_format(&image.data).ok_or_else(|| UnknownFormat(image.file_name.clone().into()))?;
+		crate::init();
+		tokio::task::spawn_blocking(move || {
+			rayon_run(move || {
+				if extension == "webp" {
+					video::decode_webp(&image.data)
+				} else {
+					let mut tmp = tempfile::Builder::new().suffix(&format!(".{extension}")).tempfile()?;
+					tmp.write_all(&image.data)?;
+					tmp.flush()?;
+					video::decode_frames(&tmp.path())
+				}
+			})
+		})
+		.await?
+	}
+
+	/// Convert the image to a whatsapp sticker: a webp image of 512×512 pixels.
+	///
+	/// Static stickers are compressed to at most 100 KB and animated ones to 500 KB,
+	/// by lowering the quality and the frame rate. Animations are cut after 10 seconds.
+	/// Return the image and whether it is animated.
+	/// Requires the `ffmpeg` feature.
+	pub async fn convert_for_whatsapp(self) -> Result<(Self, bool), Error> {
+		#[cfg(not(feature = "ffmpeg"))]
+		return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Conversion));
+		#[cfg(feature = "ffmpeg")]
+		{
+			let stem = Path::new(&self.file_name)
+				.file_stem()
+				.and_then(|stem| stem.to_str())
+				.unwrap_or("sticker")
+				.to_owned();
+			let frames = self.decode_frames().await?;
+			tokio::task::spawn_blocking(move || {
+				rayon_run(move || {
+					let frames = video::fit_square(&frames, 512)?;
+					let animated = frames.frames.len() > 1;
+					let (max_size, steps) = if animated { (500 * 1024, 1..=4) } else { (100 * 1024, 1..=1) };
+					for step in steps {
+						for quality in [90.0, 75.0, 60.0, 45.0, 30.0, 15.0] {
+							let data = video::encode_webp(&frames, quality, step, 10_000)?;
+							if data.len() <= max_size {
+								let image = Self {
+									file_name: format!("{stem}.webp"),
+									data: Arc::new(data),
+									width: 512,
+									height: 512
+								};
+								return Ok((image, animated));
+							}
+						}
+					}
+					Err(Error::TooLarge {
+						file_name: stem,
+						max_size
+					})
+				})
+			})
+			.await?
+		}
+	}
+
+	/// Convert the image to a whatsapp tray icon: a png image of 96×96 pixels showing the first frame.
+	/// Requires the `ffmpeg` feature.
+	pub async fn convert_for_whatsapp_tray(self) -> Result<Self, Error> {
+		#[cfg(not(feature = "ffmpeg"))]
+		return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Conversion));
+		#[cfg(feature = "ffmpeg")]
+		{
+			let mut frames = self.decode_frames().await?;
+			frames.frames.truncate(1);
+			tokio::task::spawn_blocking(move || {
+				rayon_run(move || {
+					let frames = video::fit_square(&frames, 96)?;
+					Ok(Self {
+						file_name: "tray.png".to_owned(),
+						data: Arc::new(video::encode_png(&frames, (96, 96))?),
+						width: 96,
+						height: 96
+					})
+				})
+			})
+			.await?
+		}
+	}
+
+	/// upload the image to matrix, if it is not a dry run
+	pub(crate) async fn import<D>(
+		self,
+		matrix_config: &Config,
+		advance_config: &ImportConfig<'_, D>
+	) -> Result<matrix::sticker::Image, Error>
+	where
+		D: database::Database + ?Sized
+	{
+		let mxc = if advance_config.dry_run {
+			#[cfg(feature = "log")]
+			{
+				warn!("  upload skipped; dryrun");
+			}
+			Mxc::new("!!! DRY_RUN !!!".to_owned(), Some(self.data.clone())) //cloning Arc is cheap
+		} else {
+			let (mxc, has_uploded) = self.upload(matrix_config, advance_config.database).await?;
+			#[cfg(feature = "log")]
+			if !has_uploded {
+				log::info!("  upload skipped; file with this hash was already uploaded");
+			}
+			#[cfg(not(feature = "log"))]
+			let _ = has_uploded; //fix unused warning
+			mxc
+		};
+		let meta_data = ponies::MetaData::try_from(self)?;
+		Ok(matrix::sticker::Image { url: mxc, meta_data })
+	}
+
 	///upload image to matrix
 	/// return mxc_url and true if image was uploaded now; false if it was already uploaded before and exist at the database
+	///
+	/// Files uploaded to another homeserver, e.g. before moving to the configured one, are uploaded again.
 	pub async fn upload<D>(&self, matrix_config: &Config, database: Option<&D>) -> Result<(Mxc, bool), Error>
 	where
-		D: database::Database
+		D: database::Database + ?Sized
 	{
 		let hash = Lazy::new(|| database::hash(&self.data));
 
 		// if database is some and datbase.unwrap().get() is also some
 		if let Some(db) = database {
 			if let Some(url) = db.get(&hash).await.map_err(Error::Database)? {
-				return Ok((url.into(), false));
+				let mxc: Mxc = url.into();
+				let server_name = mxc.parts().ok().map(|(server_name, _)| server_name);
+				if server_name.is_some() && server_name == matrix_config.server_name() {
+					return Ok((mxc, false));
+				}
 			}
 		}
 
@@ -167,3 +632,112 @@ impl Image {
 		Ok((mxc, true))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{repaint_lottie, sniff_format, Image};
+	use crate::{
+		database::{hash, Database as _, FileDatabase},
+		matrix
+	};
+	use std::{
+		io::{Read as _, Write as _},
+		net::TcpListener,
+		sync::Arc,
+		thread
+	};
+
+	/// homeserver, which answers a single upload request with `content_uri`
+	fn upload_server(content_uri: &'static str) -> (String, thread::JoinHandle<()>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let handle = thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut request = Vec::new();
+			let mut buf = [0; 1024];
+			// read the whole request, before answering it
+			loop {
+				let len = stream.read(&mut buf).unwrap();
+				request.extend_from_slice(&buf[..len]);
+				let request = String::from_utf8_lossy(&request);
+				if let Some((head, body)) = request.split_once("\r\n\r\n") {
+					let content_length = head
+						.lines()
+						.find_map(|line| line.to_lowercase().strip_prefix("content-length: ")?.parse().ok())
+						.unwrap_or(0);
+					if body.len() >= content_length {
+						assert!(head.starts_with("POST /_matrix/media/r0/upload"));
+						break;
+					}
+				}
+			}
+			let body = format!(r#"{{"content_uri": "{content_uri}"}}"#);
+			write!(
+				stream,
+				"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+				body.len()
+			)
+			.unwrap();
+		});
+		(url, handle)
+	}
+
+	#[tokio::test]
+	async fn upload_to_other_server() {
+		let dir = tempfile::tempdir().unwrap();
+		let database = FileDatabase::new(dir.path().join("uploads")).await.unwrap();
+		let image = Image {
+			file_name: "cat.png".to_owned(),
+			data: Arc::new(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x25\0\0\0\x15\x08\x06\0\0\0".to_vec()),
+			width: 37,
+			height: 21
+		};
+		// the file was uploaded to the old homeserver, e.g. by the import of the pack, which is now mirrored
+		database
+			.add(hash(&image.data), "mxc://old.example.org/cat".to_owned())
+			.await
+			.unwrap();
+		let config = |homeserver_url: String, user: &str| matrix::Config {
+			homeserver_url,
+			user: user.to_owned(),
+			access_token: "token".to_owned()
+		};
+
+		let old_config = config("none".to_owned(), "@alice:old.example.org");
+		let (mxc, uploaded) = image.upload(&old_config, Some(&database)).await.unwrap();
+		assert_eq!((mxc.url().as_str(), uploaded), ("mxc://old.example.org/cat", false));
+
+		let (url, server) = upload_server("mxc://new.example.org/cat");
+		let new_config = config(url, "@alice:new.example.org");
+		let (mxc, uploaded) = image.upload(&new_config, Some(&database)).await.unwrap();
+		server.join().unwrap();
+		assert_eq!((mxc.url().as_str(), uploaded), ("mxc://new.example.org/cat", true));
+		// the database now points to the new homeserver
+		let (mxc, uploaded) = image.upload(&new_config, Some(&database)).await.unwrap();
+		assert_eq!((mxc.url().as_str(), uploaded), ("mxc://new.example.org/cat", false));
+	}
+
+	#[test]
+	fn sniff() {
+		let png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x25\0\0\0\x15\x08\x06\0\0\0";
+		assert_eq!(sniff_format(png), Some(("png", 37, 21)));
+		assert_eq!(sniff_format(b"GIF89a\x25\0\x15\0\0\0\0;"), Some(("gif", 37, 21)));
+		let lottie = br#"{"v":"5.5.2","fr":60,"w":512,"h":512,"layers":[]}"#;
+		assert_eq!(sniff_format(lottie), Some(("lottie", 512, 512)));
+		assert_eq!(sniff_format(b"not an image"), None);
+	}
+
+	#[test]
+	fn repaint() {
+		let mut lottie = serde_json::json!({
+			"layers": [{ "shapes": [
+				{ "ty": "fl", "c": { "a": 0, "k": [0.1, 0.2, 0.3, 1] } },
+				{ "ty": "st", "c": { "a": 1, "k": [{ "t": 0, "s": [0, 0, 0, 1] }] } }
+			]}]
+		});
+		repaint_lottie(&mut lottie, [255, 0, 255]);
+		let shapes = &lottie["layers"][0]["shapes"];
+		assert_eq!(shapes[0]["c"]["k"], serde_json::json!([1.0, 0.0, 1.0, 1]));
+		assert_eq!(shapes[1]["c"]["k"][0]["s"], serde_json::json!([1.0, 0.0, 1.0, 1]));
+	}
+}
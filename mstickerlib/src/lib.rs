@@ -7,7 +7,10 @@
 pub mod database;
 pub mod error;
 pub mod image;
+pub mod local;
 pub mod matrix;
+#[cfg(test)]
+mod test_util;
 pub mod tg;
 #[cfg(feature = "ffmpeg")]
 mod video;
@@ -0,0 +1,380 @@
+//! Sticker packs from a local directory of images.
+//!
+//! Emoji and shortcode of the stickers are taken from the file names:
+//! `03_happy cat 😺.png` results in the emoji `😺` and the shortcode `happy_cat`.
+//! A leading number is only used for sorting and is removed.
+//! Alternatively the values can be set by a [Manifest].
+//! Stickers of a chat history exported by Telegram Desktop can be read by [LocalPack::read_chat_export].
+
+use crate::{
+	database::Database,
+	error::Error,
+	image::Image,
+	matrix::{self, sticker_formats::ponies::Usage},
+	tg::ImportConfig
+};
+use derive_getters::Getters;
+use futures_util::future::join_all;
+use serde::Deserialize;
+use std::{
+	collections::HashSet,
+	io,
+	path::{Path, PathBuf}
+};
+
+#[cfg(feature = "log")]
+use log::{info, warn};
+
+mod chat_export;
+
+/// Optional description of a local sticker pack, to overwrite the values derived from the file names.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[non_exhaustive]
+pub struct Manifest {
+	/// title of the pack; defaults to the name of the directory
+	pub title: Option<String>,
+	/// unique id of the pack; defaults to `dir_` followed by the name of the directory
+	pub id: Option<String>,
+	/// Stickers in the order they should be shown.
+	/// Images, which are not listed here, are added afterwards ordered by their file name.
+	#[serde(default)]
+	pub stickers: Vec<ManifestSticker>
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ManifestSticker {
+	/// file name of the image relative to the directory
+	pub file: String,
+	pub body: Option<String>,
+	pub emoji: Option<Vec<String>>,
+	pub shortcode: Option<String>
+}
+
+#[derive(Clone, Debug, Getters)]
+#[non_exhaustive]
+pub struct LocalSticker {
+	path: PathBuf,
+	/// description of the sticker
+	body: String,
+	/// emoji associated with the sticker
+	emoji: Vec<String>,
+	/// abbreviation for the sticker, to be used inline
+	shortcode: Option<String>
+}
+
+#[derive(Clone, Debug, Getters)]
+#[non_exhaustive]
+pub struct LocalPack {
+	title: String,
+	id: String,
+	stickers: Vec<LocalSticker>
+}
+
+/// chars with the unicode property `Extended_Pictographic` outside of the emoji blocks, see
+/// <https://www.unicode.org/Public/UCD/latest/ucd/emoji/emoji-data.txt>
+const PICTOGRAPHIC: &[(u32, u32)] = &[
+	(0x00A9, 0x00A9),
+	(0x00AE, 0x00AE),
+	(0x203C, 0x203C),
+	(0x2049, 0x2049),
+	(0x2122, 0x2122),
+	(0x2139, 0x2139),
+	(0x2194, 0x2199),
+	(0x21A9, 0x21AA),
+	(0x231A, 0x231B),
+	(0x2328, 0x2328),
+	(0x2388, 0x2388),
+	(0x23CF, 0x23CF),
+	(0x23E9, 0x23F3),
+	(0x23F8, 0x23FA),
+	(0x24C2, 0x24C2),
+	(0x25AA, 0x25AB),
+	(0x25B6, 0x25B6),
+	(0x25C0, 0x25C0),
+	(0x25FB, 0x25FE),
+	(0x2600, 0x2605),
+	(0x2607, 0x2612),
+	(0x2614, 0x2685),
+	(0x2690, 0x2705),
+	(0x2708, 0x2712),
+	(0x2714, 0x2714),
+	(0x2716, 0x2716),
+	(0x271D, 0x271D),
+	(0x2721, 0x2721),
+	(0x2728, 0x2728),
+	(0x2733, 0x2734),
+	(0x2744, 0x2744),
+	(0x2747, 0x2747),
+	(0x274C, 0x274C),
+	(0x274E, 0x274E),
+	(0x2753, 0x2755),
+	(0x2757, 0x2757),
+	(0x2763, 0x2767),
+	(0x2795, 0x2797),
+	(0x27A1, 0x27A1),
+	(0x27B0, 0x27B0),
+	(0x27BF, 0x27BF),
+	(0x2934, 0x2935),
+	(0x2B05, 0x2B07),
+	(0x2B1B, 0x2B1C),
+	(0x2B50, 0x2B50),
+	(0x2B55, 0x2B55),
+	(0x3030, 0x3030),
+	(0x303D, 0x303D),
+	(0x3297, 0x3297),
+	(0x3299, 0x3299)
+];
+
+fn is_emoji(c: char) -> bool {
+	let c = c as u32;
+	matches!(c, 0x1F000..=0x1FAFF | 0x1FC00..=0x1FFFD)
+		|| PICTOGRAPHIC.iter().any(|(start, end)| (*start..=*end).contains(&c))
+}
+
+/// chars, which modify the previous emoji
+fn is_emoji_modifier(c: char) -> bool {
+	matches!(c as u32, 0xFE0F | 0x200D | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F)
+}
+
+/// split a file stem into the emoji and the remaining text
+fn parse_file_stem(stem: &str) -> (Vec<String>, String) {
+	let mut emoji: Vec<String> = Vec::new();
+	let mut text = String::new();
+	let mut after_joiner = false;
+	for c in stem.chars() {
+		if is_emoji_modifier(c) && !emoji.is_empty() {
+			emoji.last_mut().unwrap().push(c);
+			after_joiner = c == '\u{200D}';
+		} else if is_emoji(c) {
+			match emoji.last_mut() {
+				Some(last) if after_joiner => last.push(c),
+				_ => emoji.push(c.to_string())
+			}
+			after_joiner = false;
+		} else {
+			text.push(c);
+			after_joiner = false;
+		}
+	}
+	// remove number used for sorting
+	let text = text.trim().trim_start_matches(|c: char| c.is_ascii_digit());
+	let text = text.trim_start_matches(['_', '-', ' ']).trim_end_matches(['_', '-', ' ']);
+	(emoji, text.split_whitespace().collect::<Vec<_>>().join("_").to_lowercase())
+}
+
+/// check if the content of a `.json` file is a lottie animation and not some other json file, like a stickerpack
+fn is_lottie(data: &[u8]) -> bool {
+	serde_json::from_slice::<serde_json::Value>(data).is_ok_and(|value| {
+		value.get("layers").is_some_and(|layers| layers.is_array())
+			&& ["v", "fr", "w", "h"].iter().all(|key| value.get(key).is_some())
+	})
+}
+
+impl LocalSticker {
+	fn from_path(path: PathBuf, manifest: Option<&ManifestSticker>) -> Self {
+		let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+		let (emoji, shortcode) = parse_file_stem(stem);
+		let shortcode = manifest
+			.and_then(|manifest| manifest.shortcode.clone())
+			.or_else(|| (!shortcode.is_empty()).then_some(shortcode));
+		let emoji = manifest.and_then(|manifest| manifest.emoji.clone()).unwrap_or(emoji);
+		let body = manifest
+			.and_then(|manifest| manifest.body.clone())
+			.or_else(|| shortcode.clone())
+			.unwrap_or_else(|| emoji.join(""));
+		Self {
+			path,
+			body,
+			emoji,
+			shortcode
+		}
+	}
+
+	/// Import sticker to matrix
+	pub async fn import<'a, D>(
+		&self,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Result<matrix::sticker::Sticker, Error>
+	where
+		D: Database + ?Sized
+	{
+		#[cfg(feature = "log")]
+		info!("convert sticker {}", self.path.display());
+		let image = Image::from_file(&self.path).await?.convert(advance_config).await?;
+		#[cfg(feature = "log")]
+		info!("  upload sticker {}", self.path.display());
+		let image = image.import(matrix_config, advance_config).await?;
+		Ok(matrix::sticker::Sticker {
+			body: self.body.clone(),
+			image,
+			thumbnail: None,
+			premium_animation: None,
+			emoticon: self.shortcode.clone(),
+			emoji: self.emoji.clone(),
+			usage: [Usage::Sticker].into_iter().collect(),
+			tg_sticker: None
+		})
+	}
+}
+
+impl LocalPack {
+	/// Read the stickers of a directory.
+	///
+	/// Hidden files and files with an unsupported extension are ignored.
+	/// `.json` files are only used if they contain a lottie animation,
+	/// the content of all other files is only read by [LocalPack::import].
+	pub async fn read<P>(dir: P, manifest: Option<Manifest>) -> io::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		const EXTENSIONS: [&str; 7] = ["png", "gif", "webp", "webm", "tgs", "lottie", "json"];
+		let dir = dir.as_ref();
+		let manifest = manifest.unwrap_or_default();
+		let mut files = Vec::new();
+		let mut entries = tokio::fs::read_dir(dir).await?;
+		while let Some(entry) = entries.next_entry().await? {
+			let Ok(file_name) = entry.file_name().into_string() else {
+				continue;
+			};
+			let extension = Path::new(&file_name).extension().and_then(|extension| extension.to_str());
+			if file_name.starts_with('.')
+				|| !extension.is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+				|| !entry.file_type().await?.is_file()
+			{
+				continue;
+			}
+			if extension.is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+				&& !is_lottie(&tokio::fs::read(entry.path()).await?)
+			{
+				#[cfg(feature = "log")]
+				info!("skip {file_name:?}, which is no lottie animation");
+				continue;
+			}
+			files.push(file_name);
+		}
+		files.sort();
+
+		let mut stickers = Vec::new();
+		for sticker in &manifest.stickers {
+			if !files.contains(&sticker.file) {
+				return Err(io::Error::new(
+					io::ErrorKind::NotFound,
+					format!("sticker {:?} of the manifest does not exist", sticker.file)
+				));
+			}
+			stickers.push(LocalSticker::from_path(dir.join(&sticker.file), Some(sticker)));
+		}
+		let listed: HashSet<&str> = manifest.stickers.iter().map(|sticker| sticker.file.as_str()).collect();
+		for file in files.iter().filter(|file| !listed.contains(file.as_str())) {
+			stickers.push(LocalSticker::from_path(dir.join(file), None));
+		}
+
+		let dir_name = dir
+			.canonicalize()?
+			.file_name()
+			.and_then(|name| name.to_str())
+			.unwrap_or("stickers")
+			.to_owned();
+		Ok(Self {
+			title: manifest.title.unwrap_or_else(|| dir_name.clone()),
+			id: manifest.id.unwrap_or_else(|| format!("dir_{dir_name}")),
+			stickers
+		})
+	}
+
+	/// Import this pack to matrix.
+	/// Return the whole pack or the successful part and the errors with the position of the failed stickers.
+	/// [ImportConfig::journal] is ignored, since it is only supported for telegram packs.
+	pub async fn import<'a, D>(
+		&self,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Result<matrix::stickerpack::StickerPack, (matrix::stickerpack::StickerPack, Vec<(usize, Error)>)>
+	where
+		D: Database + ?Sized
+	{
+		#[cfg(feature = "log")]
+		info!(
+			"import local stickerpack {:?}    {{{} Stickers}}",
+			self.title,
+			self.stickers.len()
+		);
+		let results = join_all(
+			self.stickers
+				.iter()
+				.map(|sticker| sticker.import(matrix_config, advance_config))
+		)
+		.await;
+		let mut stickers = Vec::new();
+		let mut errors = Vec::new();
+		for (i, result) in results.into_iter().enumerate() {
+			match result {
+				Ok(sticker) => stickers.push(sticker),
+				Err(err) => errors.push((i, err))
+			}
+		}
+		let pack = matrix::stickerpack::StickerPack {
+			title: self.title.clone(),
+			id: self.id.clone(),
+			tg_pack: None,
+			avatar: None,
+			stickers
+		};
+		#[cfg(feature = "log")]
+		if pack.stickers.is_empty() {
+			warn!("imported pack {} is empty", self.title);
+		}
+		if errors.is_empty() {
+			Ok(pack)
+		} else {
+			Err((pack, errors))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{is_emoji, is_lottie, parse_file_stem};
+
+	#[test]
+	fn file_stem() {
+		assert_eq!(
+			parse_file_stem("03_Happy cat 😺"),
+			(vec!["😺".to_owned()], "happy_cat".to_owned())
+		);
+		assert_eq!(
+			parse_file_stem("👍🏽😺❤️"),
+			(vec!["👍🏽".to_owned(), "😺".to_owned(), "❤️".to_owned()], String::new())
+		);
+		assert_eq!(parse_file_stem("🏳️‍🌈 flag"), (vec!["🏳️‍🌈".to_owned()], "flag".to_owned()));
+	}
+
+	#[test]
+	fn emoji() {
+		for c in [
+			'‼', '⁉', '⌚', '⏰', '©', '®', '™', 'ℹ', '↔', '▶', '☀', '✅', '✨', '❤', '⬆', '⭐', '〰', '㊗', '😺', '🫠'
+		] {
+			assert!(is_emoji(c), "{c:?} is an emoji");
+		}
+		for c in ['a', '1', '#', '_', '→', '✓', '€', '…', 'ä', '中'] {
+			assert!(!is_emoji(c), "{c:?} is no emoji");
+		}
+		assert_eq!(
+			parse_file_stem("02 ‼ wait ⏰"),
+			(vec!["‼".to_owned(), "⏰".to_owned()], "wait".to_owned())
+		);
+	}
+
+	#[test]
+	fn lottie_json() {
+		assert!(is_lottie(
+			br#"{"v":"5.5.2","fr":60,"ip":0,"op":180,"w":512,"h":512,"layers":[]}"#
+		));
+		assert!(!is_lottie(br#"{"title":"Cats","id":"tg_cats","stickers":[]}"#));
+		assert!(!is_lottie(br#"{"stickers":[{"file":"cat.png"}]}"#));
+		assert!(!is_lottie(b"[]"));
+		assert!(!is_lottie(b"no json"));
+	}
+}
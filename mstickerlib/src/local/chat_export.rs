@@ -0,0 +1,180 @@
+//! Stickers of a chat history, which was exported by Telegram Desktop as json.
+//!
+//! The export does not contain the pack of a sticker,
+//! so all stickers of a chat are grouped into one pack named after the chat.
+
+use super::{LocalPack, LocalSticker};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+	collections::HashSet,
+	io,
+	path::{Component, Path, PathBuf}
+};
+
+#[cfg(feature = "log")]
+use log::warn;
+
+/// file of the export containing the messages
+const RESULT_FILE: &str = "result.json";
+
+#[derive(Debug, Deserialize)]
+struct Message {
+	media_type: Option<String>,
+	/// path of the file relative to the export directory,
+	/// or a note if the file was not exported
+	file: Option<String>,
+	sticker_emoji: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+	name: Option<String>,
+	#[serde(rename = "type")]
+	chat_type: Option<String>,
+	id: Option<i64>,
+	#[serde(default)]
+	messages: Vec<Message>
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatList {
+	list: Vec<Chat>
+}
+
+/// Either the export of a single chat, or of all chats with the chats at `chats` and `left_chats`.
+#[derive(Debug, Deserialize)]
+struct Export {
+	#[serde(flatten)]
+	chat: Chat,
+	chats: Option<ChatList>,
+	left_chats: Option<ChatList>
+}
+
+/// return the stickers of the messages, each distinct file only once
+async fn stickers(dir: &Path, messages: &[Message]) -> io::Result<Vec<LocalSticker>> {
+	let mut hashes = HashSet::new();
+	let mut stickers = Vec::new();
+	for message in messages {
+		if message.media_type.as_deref() != Some("sticker") {
+			continue;
+		}
+		let Some(file) = &message.file else {
+			continue;
+		};
+		// the path must stay inside of the export
+		if !Path::new(file)
+			.components()
+			.all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+		{
+			#[cfg(feature = "log")]
+			warn!("skip sticker {file:?}, which is outside of the export");
+			continue;
+		}
+		let path: PathBuf = dir.join(file);
+		if !path.is_file() {
+			// the stickers were not selected at the export
+			#[cfg(feature = "log")]
+			warn!("skip sticker {file:?}, which is not part of the export");
+			continue;
+		}
+		// the same sticker is stored again for every message
+		let hash = Sha256::digest(tokio::fs::read(&path).await?);
+		if !hashes.insert(hash) {
+			continue;
+		}
+		let emoji: Vec<String> = message.sticker_emoji.iter().cloned().collect();
+		stickers.push(LocalSticker {
+			path,
+			body: emoji.join(""),
+			emoji,
+			shortcode: None
+		});
+	}
+	Ok(stickers)
+}
+
+impl LocalPack {
+	/// Read the stickers of a chat history exported by Telegram Desktop in the json format.
+	///
+	/// `dir` is the directory containing the `result.json` of the export.
+	/// Return one pack for each chat, which contains stickers.
+	/// Like [LocalPack::read], the stickers are only read from disk, nothing is uploaded.
+	pub async fn read_chat_export<P>(dir: P) -> io::Result<Vec<Self>>
+	where
+		P: AsRef<Path>
+	{
+		let dir = dir.as_ref();
+		let data = tokio::fs::read(dir.join(RESULT_FILE)).await?;
+		let export: Export = serde_json::from_slice(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+		let chats = std::iter::once(export.chat)
+			.chain(export.chats.into_iter().flat_map(|chats| chats.list))
+			.chain(export.left_chats.into_iter().flat_map(|chats| chats.list));
+
+		let mut packs = Vec::new();
+		for chat in chats {
+			let stickers = stickers(dir, &chat.messages).await?;
+			if stickers.is_empty() {
+				continue;
+			}
+			let title = match (chat.name, chat.chat_type.as_deref()) {
+				(Some(name), _) => name,
+				(None, Some("saved_messages")) => "Saved Messages".to_owned(),
+				(None, _) => format!("Chat {}", chat.id.unwrap_or_default())
+			};
+			packs.push(Self {
+				id: format!("tg_chat_{}", chat.id.unwrap_or_default()),
+				title,
+				stickers
+			});
+		}
+		Ok(packs)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::local::LocalPack;
+	use std::fs;
+
+	#[tokio::test]
+	async fn chat_export() {
+		let root = tempfile::tempdir().unwrap();
+		// files outside of the export must not be read
+		let secret = root.path().join("secret.webp");
+		fs::write(&secret, b"secret").unwrap();
+		let dir = root.path().join("export");
+		fs::create_dir(&dir).unwrap();
+		let dir = dir.as_path();
+		fs::create_dir(dir.join("stickers")).unwrap();
+		fs::write(dir.join("stickers/sticker.webp"), b"cat").unwrap();
+		fs::write(dir.join("stickers/sticker (2).webp"), b"cat").unwrap();
+		fs::write(dir.join("stickers/AnimatedSticker.tgs"), b"dog").unwrap();
+		fs::write(
+			dir.join("result.json"),
+			r#"{
+				"about": "full export",
+				"chats": { "list": [
+					{ "name": "Alice", "type": "personal_chat", "id": 42, "messages": [
+						{ "id": 1, "type": "message", "file": "stickers/sticker.webp", "media_type": "sticker", "sticker_emoji": "😺" },
+						{ "id": 2, "type": "message", "text": "hi" },
+						{ "id": 3, "type": "message", "file": "stickers/sticker (2).webp", "media_type": "sticker", "sticker_emoji": "😺" },
+						{ "id": 4, "type": "message", "file": "stickers/AnimatedSticker.tgs", "media_type": "sticker", "sticker_emoji": "🐶" },
+						{ "id": 5, "type": "message", "file": "(File not included. Change data exporting settings to download.)", "media_type": "sticker" },
+						{ "id": 6, "type": "message", "file": "../secret.webp", "media_type": "sticker", "sticker_emoji": "🔑" },
+						{ "id": 7, "type": "message", "file": "SECRET", "media_type": "sticker", "sticker_emoji": "🔒" }
+					]},
+					{ "type": "saved_messages", "id": 1, "messages": [] }
+				]}
+			}"#
+			.replace("SECRET", secret.to_str().unwrap())
+		)
+		.unwrap();
+		let packs = LocalPack::read_chat_export(dir).await.unwrap();
+		assert_eq!(packs.len(), 1);
+		assert_eq!(packs[0].title(), "Alice");
+		assert_eq!(packs[0].id(), "tg_chat_42");
+		let emoji: Vec<_> = packs[0].stickers().iter().map(|sticker| sticker.emoji().join("")).collect();
+		assert_eq!(emoji, ["😺", "🐶"]);
+	}
+}
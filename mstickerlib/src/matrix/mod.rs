@@ -1,10 +1,11 @@
+pub mod room;
 pub mod sticker;
 pub mod sticker_formats;
 pub mod stickerpack;
 mod stickerpicker;
 
 use crate::{
-	error::{Error, MatrixError},
+	error::{Error, InvalidMxcUrl, MatrixError},
 	CLIENT
 };
 use derive_getters::Getters;
@@ -34,12 +35,54 @@ impl Mxc {
 		Self { url, data }
 	}
 
+	/// server name and media id of the url
+	pub fn parts(&self) -> Result<(&str, &str), InvalidMxcUrl> {
+		self.url
+			.strip_prefix("mxc://")
+			.and_then(|parts| parts.split_once('/'))
+			.filter(|(server_name, media_id)| !server_name.is_empty() && !media_id.is_empty() && !media_id.contains('/'))
+			.ok_or_else(|| InvalidMxcUrl(self.url.clone()))
+	}
+
 	/// fetch data, if not cached
-	pub async fn fetch_data(&self) -> &Vec<u8> {
+	pub async fn fetch_data(&mut self, matrix: &Config) -> Result<Arc<Vec<u8>>, Error> {
 		if let Some(data) = &self.data {
-			return data;
+			return Ok(data.clone());
 		};
-		unimplemented!() //TODO
+		let (server_name, media_id) = self.parts()?;
+		// authenticated media endpoint; older homeservers only support the legacy one
+		let mut answer = CLIENT
+			.get()
+			.get(format!(
+				"{}/_matrix/client/v1/media/download/{server_name}/{media_id}",
+				matrix.homeserver_url
+			))
+			.bearer_auth(&matrix.access_token)
+			.send()
+			.await?;
+		if answer.status() == 404 || answer.status() == 405 {
+			answer = CLIENT
+				.get()
+				.get(format!(
+					"{}/_matrix/media/r0/download/{server_name}/{media_id}",
+					matrix.homeserver_url
+				))
+				.bearer_auth(&matrix.access_token)
+				.send()
+				.await?;
+		}
+		if answer.status() != 200 {
+			let status = answer.status();
+			let error: Result<MatrixApiError, _> = answer.json().await;
+			return Err(Error::MatrixUpload(MatrixError {
+				status_code: status,
+				filename: None,
+				matrix_error: error
+			}));
+		}
+		let data = Arc::new(answer.bytes().await?.to_vec());
+		self.data = Some(data.clone());
+		Ok(data)
 	}
 }
 impl From<String> for Mxc {
@@ -106,6 +149,13 @@ pub struct Config {
 	pub access_token: String
 }
 
+impl Config {
+	/// server name of the homeserver, taken from the user id; media uploaded to the homeserver have this server name
+	pub fn server_name(&self) -> Option<&str> {
+		self.user.split_once(':').map(|(_, server_name)| server_name)
+	}
+}
+
 /// see <https://spec.matrix.org/latest/client-server-api/#standard-error-response>
 #[derive(Debug, Deserialize, Error)]
 #[error("Matrix api request was not successful: {errcode} {error}")]
@@ -118,11 +168,18 @@ pub struct MatrixApiError {
 	pub retry_after_ms: Option<u32>
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Getters)]
 pub struct Whoami {
 	user_id: String,
-	device_id: String
+	device_id: Option<String>
+}
+
+/// see <https://spec.matrix.org/latest/client-server-api/#get_matrixclientv1mediaconfig>
+#[derive(Debug, Deserialize, Getters)]
+pub struct MediaConfig {
+	/// maximum size of an upload in bytes, if the homeserver has a limit
+	#[serde(rename = "m.upload.size")]
+	upload_size: Option<u64>
 }
 
 #[derive(Debug, Deserialize)]
@@ -176,6 +233,36 @@ pub async fn whoami(matrix: &Config) -> Result<Whoami, Error> {
 	}
 }
 
+/// get the configuration of the content repository, like the maximum upload size
+pub async fn media_config(matrix: &Config) -> Result<MediaConfig, Error> {
+	// authenticated media endpoint; older homeservers only support the legacy one
+	let mut answer = CLIENT
+		.get()
+		.get(format!("{}/_matrix/client/v1/media/config", matrix.homeserver_url))
+		.bearer_auth(&matrix.access_token)
+		.send()
+		.await?;
+	if answer.status() == 404 || answer.status() == 405 {
+		answer = CLIENT
+			.get()
+			.get(format!("{}/_matrix/media/r0/config", matrix.homeserver_url))
+			.bearer_auth(&matrix.access_token)
+			.send()
+			.await?;
+	}
+	if answer.status() != 200 {
+		let status = answer.status();
+		let error: Result<MatrixApiError, _> = answer.json().await;
+		Err(Error::MatrixUpload(MatrixError {
+			status_code: status,
+			filename: None,
+			matrix_error: error
+		}))
+	} else {
+		Ok(answer.json().await?)
+	}
+}
+
 pub(crate) async fn upload(matrix: &Config, filename: &String, data: Arc<Vec<u8>>, mimetype: &str) -> Result<Mxc, Error> {
 	let mut mxc = upload_ref(matrix, filename, data.as_slice(), mimetype).await?;
 	mxc.data = Some(data);
@@ -0,0 +1,510 @@
+//! Minimal client api to take part in rooms: sync, read the history, join rooms, send messages and set room emotes.
+
+use super::{
+	sticker::{Image, Sticker},
+	sticker_formats::{
+		maunium,
+		ponies::{self, MetaData, Usage}
+	},
+	stickerpack::StickerPack,
+	Config, MatrixApiError, Mxc
+};
+use crate::{
+	error::{Error, MatrixError},
+	CLIENT
+};
+use derive_getters::Getters;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::atomic::{AtomicU64, Ordering},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+#[cfg(feature = "log")]
+use log::{info, warn};
+
+/// state event type of room emote packs; see [ponies](super::sticker_formats::ponies)
+pub const ROOM_EMOTES: &str = "im.ponies.room_emotes";
+
+/// Event of a room timeline; see <https://spec.matrix.org/latest/client-server-api/#room-event-format>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct RoomEvent {
+	event_id: String,
+	sender: String,
+	#[serde(rename = "type")]
+	event_type: String,
+	#[serde(default)]
+	content: Value
+}
+
+impl RoomEvent {
+	/// text of the event, if it is a `m.room.message`
+	pub fn body(&self) -> Option<&str> {
+		if self.event_type != "m.room.message" {
+			return None;
+		}
+		self.content.get("body").and_then(Value::as_str)
+	}
+
+	/// `true` if the event replaces the content of another event, e.g. an edited message
+	pub fn is_edit(&self) -> bool {
+		self.content
+			.pointer("/m.relates_to/rel_type")
+			.and_then(Value::as_str)
+			.is_some_and(|rel_type| rel_type == "m.replace")
+	}
+
+	/// Sticker of the event, if it is a `m.sticker` event with a valid content.
+	/// Missing meta data of the image is set to 0; events without url are skipped.
+	/// The emoji are only known for stickers sent with the maunium sticker picker.
+	pub fn sticker(&self) -> Option<Sticker> {
+		if self.event_type != "m.sticker" {
+			return None;
+		}
+		let content: StickerContent = match serde_json::from_value(self.content.clone()) {
+			Ok(content) => content,
+			Err(err) => {
+				#[cfg(feature = "log")]
+				warn!("skip invalid sticker event {} of {}: {err}", self.event_id, self.sender);
+				#[cfg(not(feature = "log"))]
+				let _ = err; //fix unused warning
+				return None;
+			}
+		};
+		let thumbnail = content
+			.info
+			.thumbnail_url
+			.zip(content.info.thumbnail_info)
+			.map(|(url, meta_data)| Image {
+				url,
+				meta_data: meta_data.into()
+			});
+		Some(Sticker {
+			body: content.body,
+			image: Image {
+				url: content.url,
+				meta_data: content.info.image_info.into()
+			},
+			thumbnail,
+			premium_animation: None,
+			emoticon: None,
+			emoji: content
+				.tg_sticker
+				.as_ref()
+				.map(|tg_sticker| tg_sticker.emoticons.clone())
+				.unwrap_or_default(),
+			usage: [Usage::Sticker].into_iter().collect(),
+			tg_sticker: content.tg_sticker.map(Into::into)
+		})
+	}
+}
+
+/// content of a `m.sticker` event; see <https://spec.matrix.org/latest/client-server-api/#msticker>
+#[derive(Deserialize)]
+struct StickerContent {
+	body: String,
+	url: Mxc,
+	#[serde(default)]
+	info: StickerContentInfo,
+	/// set by the maunium sticker picker
+	#[serde(rename = "net.maunium.telegram.sticker")]
+	tg_sticker: Option<maunium::TgStickerInfo>
+}
+
+#[derive(Default, Deserialize)]
+struct StickerContentInfo {
+	#[serde(flatten)]
+	image_info: StickerMetaData,
+	thumbnail_url: Option<Mxc>,
+	thumbnail_info: Option<StickerMetaData>
+}
+
+/// [MetaData] of a sticker event, which is not always set completely by clients
+#[derive(Default, Deserialize)]
+struct StickerMetaData {
+	#[serde(default)]
+	w: u32,
+	#[serde(default)]
+	h: u32,
+	#[serde(default)]
+	size: usize,
+	mimetype: Option<String>
+}
+
+impl From<StickerMetaData> for MetaData {
+	fn from(value: StickerMetaData) -> Self {
+		Self {
+			w: value.w,
+			h: value.h,
+			size: value.size,
+			mimetype: value.mimetype.unwrap_or_else(|| "application/octet-stream".to_owned())
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Timeline {
+	#[serde(default)]
+	events: Vec<RoomEvent>
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct JoinedRoom {
+	#[serde(default)]
+	timeline: Timeline
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Rooms {
+	/// joined rooms by room id
+	#[serde(default)]
+	join: HashMap<String, JoinedRoom>,
+	/// rooms, to which the user was invited, by room id
+	#[serde(default)]
+	invite: HashMap<String, Value>
+}
+
+/// Response of [sync]; see <https://spec.matrix.org/latest/client-server-api/#get_matrixclientv3sync>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Sync {
+	/// token to get the following events
+	next_batch: String,
+	#[serde(default)]
+	rooms: Rooms
+}
+
+/// Response of [messages]; see <https://spec.matrix.org/latest/client-server-api/#get_matrixclientv3roomsroomidmessages>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Messages {
+	#[serde(default)]
+	chunk: Vec<RoomEvent>,
+	/// token to get the following events; `None` if there are no more events
+	end: Option<String>
+}
+
+#[derive(Serialize)]
+struct MessagesParams<'a> {
+	dir: &'static str,
+	from: Option<&'a str>,
+	limit: u32,
+	filter: Option<&'a str>
+}
+
+#[derive(Serialize)]
+struct SyncParams<'a> {
+	since: Option<&'a str>,
+	/// milliseconds to wait for new events
+	timeout: u64,
+	filter: Option<&'a str>
+}
+
+/// return the response, or the matrix error if the request was not successful
+async fn check(answer: Response) -> Result<Response, Error> {
+	if answer.status() != 200 {
+		let status = answer.status();
+		let error: Result<MatrixApiError, _> = answer.json().await;
+		return Err(Error::MatrixUpload(MatrixError {
+			status_code: status,
+			filename: None,
+			matrix_error: error
+		}));
+	}
+	Ok(answer)
+}
+
+/// unique transaction id for sending events
+fn txn_id() -> String {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+	format!("mstickereditor{time}.{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Receive new events since the `since` token of the previous sync.
+/// Waits up to `timeout` milliseconds for new events.
+/// `filter` is a filter id or a json encoded filter.
+pub async fn sync(matrix: &Config, since: Option<&str>, timeout: u64, filter: Option<&str>) -> Result<Sync, Error> {
+	let answer = CLIENT
+		.get()
+		.get(format!("{}/_matrix/client/v3/sync", matrix.homeserver_url))
+		.bearer_auth(&matrix.access_token)
+		.query(&SyncParams { since, timeout, filter })
+		.send()
+		.await?;
+	Ok(check(answer).await?.json().await?)
+}
+
+/// Page backwards through the history of the room `room_id`, starting at `from` or at the newest event.
+/// `filter` is a json encoded room event filter.
+pub async fn messages(
+	matrix: &Config,
+	room_id: &str,
+	from: Option<&str>,
+	limit: u32,
+	filter: Option<&str>
+) -> Result<Messages, Error> {
+	let answer = CLIENT
+		.get()
+		.get(format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/messages",
+			matrix.homeserver_url
+		))
+		.bearer_auth(&matrix.access_token)
+		.query(&MessagesParams {
+			dir: "b",
+			from,
+			limit,
+			filter
+		})
+		.send()
+		.await?;
+	Ok(check(answer).await?.json().await?)
+}
+
+/// Collect the stickers sent at the room `room_id`, oldest first.
+/// Each image is only returned once, even if it was sent multiple times.
+/// At most `max_events` sticker events are read, starting with the newest one.
+pub async fn collect_stickers(matrix: &Config, room_id: &str, max_events: Option<usize>) -> Result<Vec<Sticker>, Error> {
+	let filter = json!({ "types": ["m.sticker"] }).to_string();
+	let mut events = Vec::new();
+	let mut from = None;
+	loop {
+		let page = messages(matrix, room_id, from.as_deref(), 100, Some(&filter)).await?;
+		let done = page.chunk.is_empty() || page.end.is_none();
+		events.extend(page.chunk);
+		#[cfg(feature = "log")]
+		info!("read {} sticker events of room {room_id}", events.len());
+		if done || max_events.is_some_and(|max_events| events.len() >= max_events) {
+			break;
+		}
+		from = page.end;
+	}
+	events.truncate(max_events.unwrap_or(usize::MAX));
+
+	let mut urls = HashSet::new();
+	Ok(events
+		.iter()
+		.rev()
+		.filter_map(RoomEvent::sticker)
+		.filter(|sticker| urls.insert(sticker.image.url.url().to_owned()))
+		.collect())
+}
+
+/// name of the room `room_id`, if it has one
+pub async fn room_name(matrix: &Config, room_id: &str) -> Result<Option<String>, Error> {
+	#[derive(Deserialize)]
+	struct RoomName {
+		name: String
+	}
+
+	let answer = CLIENT
+		.get()
+		.get(format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/state/m.room.name/",
+			matrix.homeserver_url
+		))
+		.bearer_auth(&matrix.access_token)
+		.send()
+		.await?;
+	if answer.status() == 404 {
+		return Ok(None);
+	}
+	let room_name: RoomName = check(answer).await?.json().await?;
+	Ok(Some(room_name.name).filter(|name| !name.is_empty()))
+}
+
+/// Check if the room `room_id` is end-to-end encrypted.
+/// The events of encrypted rooms can not be read, since mstickereditor does not support encryption.
+pub async fn is_encrypted(matrix: &Config, room_id: &str) -> Result<bool, Error> {
+	let answer = CLIENT
+		.get()
+		.get(format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/state/m.room.encryption/",
+			matrix.homeserver_url
+		))
+		.bearer_auth(&matrix.access_token)
+		.send()
+		.await?;
+	if answer.status() == 404 {
+		return Ok(false);
+	}
+	check(answer).await?;
+	Ok(true)
+}
+
+/// join the room `room_id`, the user must be invited if the room is not public
+pub async fn join_room(matrix: &Config, room_id: &str) -> Result<(), Error> {
+	let answer = CLIENT
+		.get()
+		.post(format!("{}/_matrix/client/v3/rooms/{room_id}/join", matrix.homeserver_url))
+		.bearer_auth(&matrix.access_token)
+		.json(&json!({}))
+		.send()
+		.await?;
+	check(answer).await?;
+	Ok(())
+}
+
+/// Send a text message to the room `room_id`, optional as reply to the event `reply_to`.
+/// Return the event id of the message.
+pub async fn send_message(matrix: &Config, room_id: &str, text: &str, reply_to: Option<&str>) -> Result<String, Error> {
+	#[derive(Deserialize)]
+	struct EventId {
+		event_id: String
+	}
+
+	let mut content = json!({
+		"msgtype": "m.notice",
+		"body": text
+	});
+	if let Some(reply_to) = reply_to {
+		content["m.relates_to"] = json!({ "m.in_reply_to": { "event_id": reply_to } });
+	}
+	let answer = CLIENT
+		.get()
+		.put(format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/send/m.room.message/{}",
+			matrix.homeserver_url,
+			txn_id()
+		))
+		.bearer_auth(&matrix.access_token)
+		.json(&content)
+		.send()
+		.await?;
+	let event: EventId = check(answer).await?.json().await?;
+	Ok(event.event_id)
+}
+
+/// Publish the pack as room emotes of the room `room_id`.
+/// Each pack of a room needs its own `state_key`, an existing pack with the same key is replaced.
+/// The user needs the permission to send state events.
+pub async fn set_room_emotes(matrix: &Config, room_id: &str, state_key: &str, pack: StickerPack) -> Result<(), Error> {
+	let pack = ponies::StickerPack::from(pack);
+	let answer = CLIENT
+		.get()
+		.put(format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/state/{ROOM_EMOTES}/{state_key}",
+			matrix.homeserver_url
+		))
+		.bearer_auth(&matrix.access_token)
+		.json(&pack)
+		.send()
+		.await?;
+	check(answer).await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{RoomEvent, Sync};
+	use serde_json::json;
+
+	#[test]
+	fn deserialize_sync() {
+		let sync: Sync = serde_json::from_str(
+			r#"{
+				"next_batch": "s72595_4483_1934",
+				"rooms": {
+					"join": {
+						"!room:example.org": {
+							"timeline": {
+								"events": [{
+									"event_id": "$1",
+									"sender": "@alice:example.org",
+									"type": "m.room.message",
+									"content": { "msgtype": "m.text", "body": "https://t.me/addstickers/Cats" }
+								}, {
+									"event_id": "$2",
+									"sender": "@alice:example.org",
+									"type": "m.room.member",
+									"content": { "membership": "join", "body": "not a message" }
+								}, {
+									"event_id": "$3",
+									"sender": "@alice:example.org",
+									"type": "m.room.message",
+									"content": {
+										"msgtype": "m.text",
+										"body": "* https://t.me/addstickers/Cats",
+										"m.new_content": { "msgtype": "m.text", "body": "https://t.me/addstickers/Cats" },
+										"m.relates_to": { "rel_type": "m.replace", "event_id": "$1" }
+									}
+								}]
+							}
+						}
+					},
+					"invite": { "!other:example.org": {} }
+				}
+			}"#
+		)
+		.unwrap();
+		assert_eq!(sync.next_batch(), "s72595_4483_1934");
+		let events: Vec<&RoomEvent> = sync.rooms().join()["!room:example.org"].timeline().events().iter().collect();
+		assert_eq!(events[0].body(), Some("https://t.me/addstickers/Cats"));
+		assert!(!events[0].is_edit());
+		assert_eq!(events[1].body(), None);
+		assert_eq!(events[1].event_type(), "m.room.member");
+		assert!(events[2].is_edit());
+		assert!(sync.rooms().invite().contains_key("!other:example.org"));
+	}
+
+	#[test]
+	fn sticker_event() {
+		let event: RoomEvent = serde_json::from_value(json!({
+			"event_id": "$1",
+			"sender": "@alice:example.org",
+			"type": "m.sticker",
+			"content": {
+				"body": "😺",
+				"url": "mxc://example.org/cat",
+				"info": {
+					"w": 256, "h": 256, "size": 1000, "mimetype": "image/webp",
+					"thumbnail_url": "mxc://example.org/cat_thumb",
+					"thumbnail_info": { "w": 128, "h": 128, "size": 100, "mimetype": "image/png" }
+				}
+			}
+		}))
+		.unwrap();
+		let sticker = event.sticker().unwrap();
+		assert_eq!(sticker.body, "😺");
+		assert_eq!(sticker.image.url.url(), "mxc://example.org/cat");
+		assert_eq!(sticker.image.meta_data.w, 256);
+		assert_eq!(sticker.thumbnail.unwrap().meta_data.mimetype, "image/png");
+		// missing meta data is set to 0
+		let event: RoomEvent = serde_json::from_value(json!({
+			"event_id": "$2",
+			"sender": "@alice:example.org",
+			"type": "m.sticker",
+			"content": { "body": "cat", "url": "mxc://example.org/cat", "info": { "w": 256, "thumbnail_url": "mxc://example.org/cat_thumb", "thumbnail_info": {} } }
+		}))
+		.unwrap();
+		let sticker = event.sticker().unwrap();
+		assert_eq!(sticker.image.meta_data.w, 256);
+		assert_eq!(sticker.image.meta_data.size, 0);
+		assert_eq!(sticker.thumbnail.unwrap().meta_data.w, 0);
+		let event: RoomEvent = serde_json::from_value(json!({
+			"event_id": "$3",
+			"sender": "@alice:example.org",
+			"type": "m.sticker",
+			"content": { "body": "cat", "url": "mxc://example.org/cat" }
+		}))
+		.unwrap();
+		assert_eq!(event.sticker().unwrap().image.meta_data.h, 0);
+		// stickers without url are skipped
+		let event: RoomEvent = serde_json::from_value(json!({
+			"event_id": "$4",
+			"sender": "@alice:example.org",
+			"type": "m.sticker",
+			"content": { "body": "cat", "info": { "w": 256 } }
+		}))
+		.unwrap();
+		assert!(event.sticker().is_none());
+	}
+}
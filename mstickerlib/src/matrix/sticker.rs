@@ -4,16 +4,27 @@ use super::{
 };
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+fn default_usage() -> HashSet<ponies::Usage> {
+	[ponies::Usage::Sticker].into_iter().collect()
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Sticker {
 	pub body: String,
 	pub image: Image,
 	pub thumbnail: Option<Image>,
+	///full-screen effect of premium telegram stickers
+	#[serde(default)]
+	pub premium_animation: Option<Image>,
 	///abbreviation for the sticker, to be used inline
 	pub emoticon: Option<String>,
 	///unicode emoji with are assioted with the sticker
 	pub emoji: Vec<String>,
+	///how the image can be used; custom emoji are only used as emoticon
+	#[serde(default = "default_usage")]
+	pub usage: HashSet<ponies::Usage>,
 	pub tg_sticker: Option<TgStickerInfo>
 }
 
@@ -31,6 +42,9 @@ pub struct TgStickerInfo {
 	///pack where the sticker is from
 	pub pack_name: String,
 	pub bot_api_id: Option<String>,
+	/// unique id of the bot api, which does not change over time
+	#[serde(default)]
+	pub bot_api_unique_id: Option<String>,
 	pub client_api_id: Option<String>,
 	pub emoji: Vec<String>,
 	pub index: Option<usize>
@@ -41,6 +55,7 @@ impl From<maunium::TgStickerInfo> for TgStickerInfo {
 		Self {
 			pack_name: value.pack.short_name,
 			bot_api_id: None,
+			bot_api_unique_id: None,
 			client_api_id: Some(value.id),
 			emoji: value.emoticons,
 			index: None
@@ -59,13 +74,18 @@ impl From<maunium::Sticker> for Sticker {
 			meta_data: value.info.thumbnail_info
 		};
 		let thumbnail = if image == thumbnail { None } else { Some(thumbnail) };
-		let tg_sticker: Option<TgStickerInfo> = value.tg_sticker.map(|f| f.into());
+		let tg_sticker: Option<TgStickerInfo> = value.tg_info.or_else(|| value.tg_sticker.map(|f| f.into()));
 		Self {
 			body: value.body,
 			image,
 			thumbnail,
-			emoticon: None,
-			emoji: tg_sticker.as_ref().map(|f| f.emoji.to_owned()).unwrap_or_default(),
+			premium_animation: value.premium_animation,
+			emoticon: value.emoticon,
+			emoji: match value.emoji.is_empty() {
+				true => tg_sticker.as_ref().map(|f| f.emoji.to_owned()).unwrap_or_default(),
+				false => value.emoji
+			},
+			usage: default_usage(),
 			tg_sticker
 		}
 	}
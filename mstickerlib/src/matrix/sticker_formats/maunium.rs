@@ -13,6 +13,20 @@ pub struct StickerPack {
 	pub id: String,
 	#[serde(rename = "net.maunium.telegram.pack")]
 	pub tg_pack: Option<TgPackRootInfo>,
+	/// information about the telegram pack from the bot api, used to update the pack later
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.tg_pack",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub tg_info: Option<crate::matrix::stickerpack::TgPackInfo>,
+	/// icon of the pack; the maunium stickerpicker uses the first sticker instead
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.avatar",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub avatar: Option<crate::matrix::sticker::Image>,
 	pub stickers: Vec<Sticker>
 }
 
@@ -33,7 +47,36 @@ pub struct Sticker {
 	pub msgtype: MustBe!("m.sticker"),
 	pub id: String,
 	#[serde(rename = "net.maunium.telegram.sticker")]
-	pub tg_sticker: Option<TgStickerInfo>
+	pub tg_sticker: Option<TgStickerInfo>,
+	/// information about the telegram sticker from the bot api, used to update the pack later.
+	/// `tg_sticker` is not used for this, since it is also read by the telegram bridge.
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.tg_sticker",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub tg_info: Option<crate::matrix::sticker::TgStickerInfo>,
+	/// full-screen effect of premium telegram stickers
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.premium_animation",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub premium_animation: Option<crate::matrix::sticker::Image>,
+	/// shortcode of the sticker, which is not supported by the maunium stickerpicker
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.emoticon",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub emoticon: Option<String>,
+	/// emoji of stickers, which were not imported from telegram
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.emoji",
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub emoji: Vec<String>
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -81,7 +124,15 @@ impl From<crate::matrix::sticker::Sticker> for Sticker {
 			info,
 			msgtype: Default::default(),
 			id: value.image.url.url().to_owned(),
-			tg_sticker: None
+			tg_sticker: None,
+			// the emoji of telegram stickers are already stored at `tg_info`
+			emoji: match value.tg_sticker {
+				Some(ref tg_sticker) if tg_sticker.emoji == value.emoji => Vec::new(),
+				_ => value.emoji
+			},
+			tg_info: value.tg_sticker,
+			premium_animation: value.premium_animation,
+			emoticon: value.emoticon
 		}
 	}
 }
@@ -92,6 +143,8 @@ impl From<crate::matrix::stickerpack::StickerPack> for StickerPack {
 			title: value.title,
 			id: value.id,
 			tg_pack: None,
+			tg_info: value.tg_pack,
+			avatar: value.avatar,
 			stickers: value.stickers.into_iter().map(|f| f.into()).collect()
 		}
 	}
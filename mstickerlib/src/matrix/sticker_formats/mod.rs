@@ -20,3 +20,4 @@ macro_rules! impl_from {
 
 pub mod maunium;
 pub mod ponies;
+pub mod whatsapp;
@@ -14,13 +14,27 @@ use std::collections::HashSet;
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PackInfo {
 	pub display_name: String,
-	pub avatar_url: Option<String>
+	pub avatar_url: Option<String>,
+	/// meta data of the avatar, which is not part of the ponies specification
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.avatar_info",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub avatar_info: Option<MetaData>
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StickerPack {
 	pub images: IndexMap<String, Sticker>,
-	pub pack: PackInfo
+	pub pack: PackInfo,
+	/// information about the telegram pack from the bot api, used to update the pack later
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.tg_pack",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub tg_pack: Option<matrix::stickerpack::TgPackInfo>
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -54,18 +68,46 @@ pub struct Sticker {
 	pub body: String,
 	pub info: MetaData,
 	pub url: Mxc,
-	pub usage: HashSet<Usage>
+	pub usage: HashSet<Usage>,
+	/// information about the telegram sticker from the bot api, used to update the pack later
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.tg_sticker",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub tg_sticker: Option<matrix::sticker::TgStickerInfo>,
+	/// full-screen effect of premium telegram stickers
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.premium_animation",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub premium_animation: Option<matrix::sticker::Image>,
+	/// emoji of stickers, which were not imported from telegram
+	#[serde(
+		rename = "dev.luckyturtle.mstickereditor.emoji",
+		default,
+		skip_serializing_if = "Vec::is_empty"
+	)]
+	pub emoji: Vec<String>
 }
 
-/// **Warning:** `usage` will always be set to [`Sticker`](Usage::Sticker), since
-/// [`Emoticon`](Usage::Emoticon) is only useful when paired with a string.
+/// **Warning:** the [`Emoticon`](Usage::Emoticon) usage is only useful when paired with a string.
+/// It is added by the conversion of the whole pack, if the sticker has an emoticon.
 impl From<matrix::sticker::Sticker> for Sticker {
 	fn from(value: crate::matrix::sticker::Sticker) -> Self {
 		Self {
 			body: value.body,
 			url: value.image.url,
 			info: value.image.meta_data,
-			usage: [Usage::Sticker].into_iter().collect()
+			usage: value.usage,
+			// the emoji of telegram stickers are already stored at `tg_sticker`
+			emoji: match value.tg_sticker {
+				Some(ref tg_sticker) if tg_sticker.emoji == value.emoji => Vec::new(),
+				_ => value.emoji
+			},
+			tg_sticker: value.tg_sticker,
+			premium_animation: value.premium_animation
 		}
 	}
 }
@@ -89,10 +131,115 @@ impl From<matrix::stickerpack::StickerPack> for StickerPack {
 				.collect(),
 			pack: PackInfo {
 				display_name: value.title,
-				avatar_url: None
-			}
+				avatar_url: value.avatar.as_ref().map(|avatar| avatar.url.url().to_owned()),
+				avatar_info: value.avatar.map(|avatar| avatar.meta_data)
+			},
+			tg_pack: value.tg_pack
+		}
+	}
+}
+
+/// The key of the image is used as emoticon, if the image has the [`Emoticon`](Usage::Emoticon) usage.
+impl From<StickerPack> for matrix::stickerpack::StickerPack {
+	fn from(value: StickerPack) -> Self {
+		Self {
+			id: value
+				.tg_pack
+				.as_ref()
+				.map(|tg_pack| format!("tg_name_{}", tg_pack.name))
+				.unwrap_or_else(|| value.pack.display_name.clone()),
+			title: value.pack.display_name,
+			tg_pack: value.tg_pack,
+			avatar: value.pack.avatar_url.map(|url| matrix::sticker::Image {
+				url: url.into(),
+				// avatars set by other tools have no meta data
+				meta_data: value.pack.avatar_info.unwrap_or_else(|| MetaData {
+					w: 0,
+					h: 0,
+					size: 0,
+					mimetype: "application/octet-stream".to_owned()
+				})
+			}),
+			stickers: value
+				.images
+				.into_iter()
+				.map(|(key, sticker)| matrix::sticker::Sticker {
+					body: sticker.body,
+					image: matrix::sticker::Image {
+						url: sticker.url,
+						meta_data: sticker.info
+					},
+					thumbnail: None,
+					premium_animation: sticker.premium_animation,
+					emoticon: sticker.usage.contains(&Usage::Emoticon).then_some(key),
+					emoji: match sticker.emoji.is_empty() {
+						true => sticker
+							.tg_sticker
+							.as_ref()
+							.map(|tg_sticker| tg_sticker.emoji.clone())
+							.unwrap_or_default(),
+						false => sticker.emoji
+					},
+					usage: sticker.usage,
+					tg_sticker: sticker.tg_sticker
+				})
+				.collect()
 		}
 	}
 }
 
 impl_from!(Sticker, StickerPack);
+
+#[cfg(test)]
+mod tests {
+	use super::StickerPack;
+	use crate::matrix;
+
+	#[test]
+	fn keep_emoticons() {
+		let pack: StickerPack = serde_json::from_str(
+			r#"{
+				"images": {
+					"turtle": {
+						"body": "a turtle",
+						"info": { "w": 512, "h": 512, "size": 1024, "mimetype": "image/webp" },
+						"url": "mxc://example.org/turtle",
+						"usage": ["sticker", "emoticon"],
+						"dev.luckyturtle.mstickereditor.tg_sticker": {
+							"pack_name": "turtles",
+							"bot_api_id": null,
+							"bot_api_unique_id": "AgADturtle",
+							"client_api_id": null,
+							"emoji": ["🐢"],
+							"index": 0
+						}
+					}
+				},
+				"pack": { "display_name": "Turtles", "avatar_url": null }
+			}"#
+		)
+		.unwrap();
+		let pack: matrix::stickerpack::StickerPack = pack.into();
+		assert_eq!(pack.stickers[0].emoticon.as_deref(), Some("turtle"));
+		assert_eq!(pack.stickers[0].emoji, ["🐢"]);
+		let pack: StickerPack = pack.into();
+		assert!(pack.images.contains_key("turtle"));
+		assert!(pack.images["turtle"].tg_sticker.is_some());
+		assert_eq!(pack.images["turtle"].usage.len(), 2);
+	}
+
+	#[test]
+	fn keep_avatar() {
+		let pack: StickerPack = serde_json::from_str(
+			r#"{
+				"images": {},
+				"pack": { "display_name": "Turtles", "avatar_url": "mxc://example.org/avatar" }
+			}"#
+		)
+		.unwrap();
+		let pack: matrix::stickerpack::StickerPack = pack.into();
+		assert_eq!(pack.avatar.as_ref().unwrap().url.url(), "mxc://example.org/avatar");
+		let pack: StickerPack = pack.into();
+		assert_eq!(pack.pack.avatar_url.as_deref(), Some("mxc://example.org/avatar"));
+	}
+}
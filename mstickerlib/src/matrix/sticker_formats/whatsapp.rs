@@ -0,0 +1,387 @@
+//! Stickerpacks for [WhatsApp](https://github.com/WhatsApp/stickers), saved as `.wastickers` archive,
+//! which can be imported by third party sticker apps.
+//! WhatsApp can not load stickers from matrix, so all images are downloaded and re-encoded to match the requirements of WhatsApp.
+
+use crate::{error::Error, image::Image, matrix};
+use futures_util::stream::{self, StreamExt as _};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	io::{Cursor, Write as _}
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+#[cfg(feature = "log")]
+use log::info;
+
+/// WhatsApp allows at most 30 stickers per pack
+pub const MAX_STICKERS: usize = 30;
+/// WhatsApp requires at least 3 stickers per pack
+pub const MIN_STICKERS: usize = 3;
+/// WhatsApp allows at most 3 emoji per sticker
+const MAX_EMOJI: usize = 3;
+const TRAY_FILE: &str = "tray.png";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sticker {
+	/// file name of the sticker at the archive
+	pub image_file: String,
+	pub emojis: Vec<String>
+}
+
+/// Pack of the `contents.json`; see <https://github.com/WhatsApp/stickers/tree/main/Android#modifying-the-contentsjson-file>
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StickerPack {
+	pub identifier: String,
+	pub name: String,
+	pub publisher: String,
+	pub tray_image_file: String,
+	pub image_data_version: String,
+	pub avoid_cache: bool,
+	pub animated_sticker_pack: bool,
+	pub stickers: Vec<Sticker>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Contents {
+	android_play_store_link: String,
+	ios_app_store_link: String,
+	sticker_packs: Vec<StickerPack>
+}
+
+/// `.wastickers` archive of a pack
+pub struct WaStickers {
+	pub pack: StickerPack,
+	/// zip archive containing the stickers, the tray icon, `contents.json`, `title.txt` and `author.txt`
+	pub data: Vec<u8>
+}
+
+/// keep only the chars allowed at the identifier of a pack
+fn identifier(id: &str) -> String {
+	id.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() || "_-. ".contains(c) {
+				c
+			} else {
+				'_'
+			}
+		})
+		.collect()
+}
+
+/// download an image from matrix
+async fn download(image: &matrix::sticker::Image, matrix_config: &matrix::Config) -> Result<Image, Error> {
+	let mut url = image.url.clone();
+	let data = url.fetch_data(matrix_config).await?;
+	let (_, media_id) = url.parts()?;
+	let extension = image.meta_data.mimetype.split('/').next_back().unwrap_or("bin");
+	Ok(Image {
+		file_name: format!("{media_id}.{extension}"),
+		data,
+		width: image.meta_data.w,
+		height: image.meta_data.h
+	})
+}
+
+/// create the zip archive of a pack
+fn write_archive(pack: &StickerPack, tray: &Image, stickers: &[Image]) -> Result<Vec<u8>, Error> {
+	let contents = Contents {
+		android_play_store_link: String::new(),
+		ios_app_store_link: String::new(),
+		sticker_packs: vec![pack.clone()]
+	};
+	// the images are already compressed
+	let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+	let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+	zip.start_file("contents.json", options)?;
+	zip.write_all(&serde_json::to_vec(&contents)?)?;
+	zip.start_file("title.txt", options)?;
+	zip.write_all(pack.name.as_bytes())?;
+	zip.start_file("author.txt", options)?;
+	zip.write_all(pack.publisher.as_bytes())?;
+	zip.start_file(TRAY_FILE, options)?;
+	zip.write_all(&tray.data)?;
+	for (sticker, image) in pack.stickers.iter().zip(stickers) {
+		zip.start_file(sticker.image_file.as_str(), options)?;
+		zip.write_all(&image.data)?;
+	}
+	Ok(zip.finish()?.into_inner())
+}
+
+/// Split the converted stickers into WhatsApp packs.
+///
+/// `stickers` are the positions of the stickers at `pack` and whether they are animated.
+/// Static and animated stickers are not mixed and each pack has at most [MAX_STICKERS] stickers.
+/// Return the packs together with the positions of their stickers.
+fn split_pack(
+	pack: &matrix::stickerpack::StickerPack,
+	publisher: &str,
+	default_emoji: &str,
+	stickers: &[(usize, bool)]
+) -> Vec<(StickerPack, Vec<usize>)> {
+	let positions = |animated: bool| -> Vec<usize> {
+		stickers
+			.iter()
+			.filter(|(_, other)| *other == animated)
+			.map(|(i, _)| *i)
+			.collect()
+	};
+	let (static_stickers, animated_stickers) = (positions(false), positions(true));
+	let chunks: Vec<(bool, &[usize])> = static_stickers
+		.chunks(MAX_STICKERS)
+		.map(|chunk| (false, chunk))
+		.chain(animated_stickers.chunks(MAX_STICKERS).map(|chunk| (true, chunk)))
+		.collect();
+	chunks
+		.iter()
+		.enumerate()
+		.map(|(n, (animated, chunk))| {
+			let (identifier, name) = match chunks.len() {
+				1 => (identifier(&pack.id), pack.title.clone()),
+				_ => (
+					format!("{}_{}", identifier(&pack.id), n + 1),
+					format!("{} ({})", pack.title, n + 1)
+				)
+			};
+			let stickers = chunk
+				.iter()
+				.enumerate()
+				.map(|(i, index)| {
+					let mut emojis: Vec<String> = pack.stickers[*index]
+						.emoji
+						.iter()
+						.filter(|emoji| !emoji.is_empty())
+						.take(MAX_EMOJI)
+						.cloned()
+						.collect();
+					if emojis.is_empty() {
+						emojis.push(default_emoji.to_owned());
+					}
+					Sticker {
+						image_file: format!("{:02}.webp", i + 1),
+						emojis
+					}
+				})
+				.collect();
+			let wa_pack = StickerPack {
+				identifier,
+				name,
+				publisher: publisher.to_owned(),
+				tray_image_file: TRAY_FILE.to_owned(),
+				image_data_version: "1".to_owned(),
+				avoid_cache: false,
+				animated_sticker_pack: *animated,
+				stickers
+			};
+			(wa_pack, chunk.to_vec())
+		})
+		.collect()
+}
+
+/// Create the archives of the packs created by [split_pack].
+/// The tray icon is the `avatar` or is created from the first sticker of each pack;
+/// packs, whose tray icon can not be created, are dropped.
+async fn write_archives(
+	packs: Vec<(StickerPack, Vec<usize>)>,
+	images: &HashMap<usize, Image>,
+	avatar: Option<&Image>,
+	errors: &mut Vec<(Option<usize>, Error)>
+) -> Vec<WaStickers> {
+	let mut archives = Vec::new();
+	for (wa_pack, positions) in packs {
+		let images: Vec<Image> = positions.iter().map(|i| images[i].clone()).collect();
+		let tray = match avatar {
+			Some(avatar) => avatar.clone(),
+			None => match images[0].clone().convert_for_whatsapp_tray().await {
+				Ok(tray) => tray,
+				Err(err) => {
+					errors.push((None, err));
+					continue;
+				}
+			}
+		};
+		match write_archive(&wa_pack, &tray, &images) {
+			Ok(data) => archives.push(WaStickers { pack: wa_pack, data }),
+			Err(err) => errors.push((None, err))
+		}
+	}
+	archives
+}
+
+/// Convert a stickerpack to `.wastickers` archives.
+///
+/// All stickers are downloaded and converted by [Image::convert_for_whatsapp], which requires the `ffmpeg` feature.
+/// WhatsApp does neither allow static and animated stickers at the same pack nor more than [MAX_STICKERS] stickers,
+/// so the pack is split into multiple archives if necessary.
+/// The tray icon is created from the avatar of the pack or from the first sticker of each archive.
+/// Stickers without emoji get `default_emoji`.
+/// If not all stickers could be converted, the errors are returned together with the position of the sticker at the pack.
+/// The position is `None` for errors of the tray icon.
+/// Archives with less than [MIN_STICKERS] stickers are still returned, but can not be added to WhatsApp.
+pub async fn to_wastickers(
+	pack: &matrix::stickerpack::StickerPack,
+	publisher: &str,
+	default_emoji: &str,
+	matrix_config: &matrix::Config
+) -> Result<Vec<WaStickers>, (Vec<WaStickers>, Vec<(Option<usize>, Error)>)> {
+	#[cfg(feature = "log")]
+	info!("convert {} stickers for whatsapp", pack.stickers.len());
+	let results: Vec<_> = stream::iter(pack.stickers.iter())
+		.map(|sticker| async { download(&sticker.image, matrix_config).await?.convert_for_whatsapp().await })
+		.buffered(4)
+		.collect()
+		.await;
+	let mut errors = Vec::new();
+	let mut images = HashMap::new();
+	let mut stickers = Vec::new();
+	for (i, result) in results.into_iter().enumerate() {
+		match result {
+			Ok((image, animated)) => {
+				images.insert(i, image);
+				stickers.push((i, animated));
+			},
+			Err(err) => errors.push((Some(i), err))
+		}
+	}
+	let avatar = match &pack.avatar {
+		Some(avatar) => match download(avatar, matrix_config).await {
+			Ok(avatar) => Some(avatar.convert_for_whatsapp_tray().await),
+			Err(err) => Some(Err(err))
+		},
+		None => None
+	};
+	let avatar = match avatar {
+		Some(Ok(avatar)) => Some(avatar),
+		Some(Err(err)) => {
+			errors.push((None, err));
+			None
+		},
+		None => None
+	};
+
+	let packs = split_pack(pack, publisher, default_emoji, &stickers);
+	let archives = write_archives(packs, &images, avatar.as_ref(), &mut errors).await;
+	if errors.is_empty() {
+		Ok(archives)
+	} else {
+		Err((archives, errors))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{identifier, split_pack, write_archive, write_archives, Contents, Sticker, StickerPack};
+	use crate::{image::Image, test_util::matrix_pack};
+	use std::{collections::HashMap, io::Read as _, sync::Arc};
+	use zip::ZipArchive;
+
+	fn image(name: &str) -> Image {
+		Image {
+			file_name: name.to_owned(),
+			data: Arc::new(name.as_bytes().to_vec()),
+			width: 512,
+			height: 512
+		}
+	}
+
+	#[test]
+	fn pack_identifier() {
+		assert_eq!(identifier("tg_name_Cats"), "tg_name_Cats");
+		assert_eq!(identifier("room_abc:example.org/ä"), "room_abc_example.org__");
+	}
+
+	#[test]
+	fn split() {
+		// a single pack keeps the identifier and title
+		let pack = matrix_pack(&[&["😺", "😸", "😹", "😻"], &["", "🐶"], &[]]);
+		let packs = split_pack(&pack, "Alice", "🙂", &[(0, false), (1, false), (2, false)]);
+		assert_eq!(packs.len(), 1);
+		let (wa_pack, positions) = &packs[0];
+		assert_eq!(positions, &[0, 1, 2]);
+		assert_eq!(wa_pack.identifier, "tg_name_Cats");
+		assert_eq!(wa_pack.name, "Cats");
+		assert_eq!(wa_pack.publisher, "Alice");
+		assert!(!wa_pack.animated_sticker_pack);
+		let emojis: Vec<_> = wa_pack.stickers.iter().map(|sticker| sticker.emojis.join("")).collect();
+		assert_eq!(emojis, ["😺😸😹", "🐶", "🙂"]);
+
+		// 35 static and 30 animated stickers, one of them has failed to convert
+		let pack = matrix_pack(&[&["😺"] as &[&str]; 65]);
+		let stickers: Vec<(usize, bool)> = (0..65).filter(|i| *i != 40).map(|i| (i, i >= 35)).collect();
+		let packs = split_pack(&pack, "Alice", "🙂", &stickers);
+		let summary: Vec<_> = packs
+			.iter()
+			.map(|(wa_pack, positions)| {
+				(
+					wa_pack.identifier.as_str(),
+					wa_pack.name.as_str(),
+					wa_pack.animated_sticker_pack,
+					wa_pack.stickers.len(),
+					positions[0]
+				)
+			})
+			.collect();
+		assert_eq!(summary, [
+			("tg_name_Cats_1", "Cats (1)", false, 30, 0),
+			("tg_name_Cats_2", "Cats (2)", false, 5, 30),
+			("tg_name_Cats_3", "Cats (3)", true, 29, 35)
+		]);
+		assert!(!packs[2].1.contains(&40));
+		assert_eq!(packs[1].0.stickers[0].image_file, "01.webp");
+		assert_eq!(packs[1].0.stickers[4].image_file, "05.webp");
+	}
+
+	#[tokio::test]
+	async fn drop_pack_without_tray() {
+		let pack = matrix_pack(&[&["😺"], &["🐶"]]);
+		let packs = split_pack(&pack, "Alice", "🙂", &[(0, false), (1, true)]);
+		// the images are no valid stickers, so the tray icon can not be created from them
+		let images = HashMap::from([(0, image("cat")), (1, image("dog"))]);
+		let mut errors = Vec::new();
+		let archives = write_archives(packs.clone(), &images, None, &mut errors).await;
+		assert!(archives.is_empty());
+		assert_eq!(errors.len(), 2);
+		assert!(errors.iter().all(|(position, _)| position.is_none()));
+
+		let mut errors = Vec::new();
+		let archives = write_archives(packs, &images, Some(&image("avatar")), &mut errors).await;
+		assert!(errors.is_empty());
+		assert_eq!(archives.len(), 2);
+	}
+
+	#[test]
+	fn archive() {
+		let pack = StickerPack {
+			identifier: "cats".to_owned(),
+			name: "Cats".to_owned(),
+			publisher: "Alice".to_owned(),
+			tray_image_file: "tray.png".to_owned(),
+			image_data_version: "1".to_owned(),
+			avoid_cache: false,
+			animated_sticker_pack: false,
+			stickers: vec![Sticker {
+				image_file: "01.webp".to_owned(),
+				emojis: vec!["😺".to_owned()]
+			}]
+		};
+		let data = write_archive(&pack, &image("tray"), &[image("cat")]).unwrap();
+		let mut archive = ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+		let mut read = |name: &str| {
+			let mut content = String::new();
+			archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+			content
+		};
+		let contents: Contents = serde_json::from_str(&read("contents.json")).unwrap();
+		assert_eq!(contents.sticker_packs.len(), 1);
+		let wa_pack = &contents.sticker_packs[0];
+		assert_eq!(wa_pack.identifier, "cats");
+		assert_eq!(wa_pack.tray_image_file, "tray.png");
+		assert_eq!(wa_pack.stickers[0].image_file, "01.webp");
+		assert_eq!(wa_pack.stickers[0].emojis, ["😺"]);
+		assert_eq!(read("title.txt"), "Cats");
+		assert_eq!(read("author.txt"), "Alice");
+		assert_eq!(read("tray.png"), "tray");
+		assert_eq!(read("01.webp"), "cat");
+		assert_eq!(archive.len(), 5);
+	}
+}
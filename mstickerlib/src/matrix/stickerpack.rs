@@ -1,4 +1,7 @@
-use super::{sticker::Sticker, sticker_formats::maunium};
+use super::{
+	sticker::{Image, Sticker},
+	sticker_formats::maunium
+};
 use serde::{Deserialize, Serialize};
 
 ///additonal informations about the original telegram sticker pack
@@ -6,13 +9,17 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TgPackInfo {
 	pub name: String,
-	pub title: String
+	pub title: String,
+	/// unique id of the thumbnail of the pack, used to update the avatar if telegram changes it
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub thumbnail_unique_id: Option<String>
 }
 impl From<&crate::tg::StickerPack> for TgPackInfo {
 	fn from(value: &crate::tg::StickerPack) -> Self {
 		Self {
 			name: value.name.clone(),
-			title: value.title.clone()
+			title: value.title.clone(),
+			thumbnail_unique_id: value.thumbnail.as_ref().map(|thumbnail| thumbnail.file_unique_id.clone())
 		}
 	}
 }
@@ -23,6 +30,9 @@ pub struct StickerPack {
 	///unique id
 	pub id: String,
 	pub tg_pack: Option<TgPackInfo>,
+	///image shown as icon of the pack
+	#[serde(default)]
+	pub avatar: Option<Image>,
 	pub stickers: Vec<Sticker>
 }
 
@@ -30,7 +40,8 @@ impl From<maunium::TgPackInfo> for TgPackInfo {
 	fn from(value: maunium::TgPackInfo) -> Self {
 		Self {
 			name: value.short_name.clone(),
-			title: value.short_name
+			title: value.short_name,
+			thumbnail_unique_id: None
 		}
 	}
 }
@@ -38,7 +49,8 @@ impl From<maunium::TgPackRootInfo> for TgPackInfo {
 	fn from(value: maunium::TgPackRootInfo) -> Self {
 		Self {
 			name: value.short_name.clone(),
-			title: value.short_name
+			title: value.short_name,
+			thumbnail_unique_id: None
 		}
 	}
 }
@@ -48,7 +60,8 @@ impl From<maunium::StickerPack> for StickerPack {
 		Self {
 			title: value.title,
 			id: value.id,
-			tg_pack: value.tg_pack.map(|f| f.into()),
+			tg_pack: value.tg_info.or_else(|| value.tg_pack.map(|f| f.into())),
+			avatar: value.avatar,
 			stickers: value.stickers.into_iter().map(|f| f.into()).collect()
 		}
 	}
@@ -0,0 +1,87 @@
+//! builders for the telegram and matrix packs used by the tests
+
+use crate::{matrix, tg};
+use serde_json::{json, Value};
+
+/// configs, which must not be used to connect to any server
+pub(crate) fn configs() -> (matrix::Config, tg::Config) {
+	let matrix_config = matrix::Config {
+		homeserver_url: "none".to_owned(),
+		user: "none".to_owned(),
+		access_token: "none".to_owned()
+	};
+	let tg_config = tg::Config {
+		bot_key: "none".to_owned()
+	};
+	(matrix_config, tg_config)
+}
+
+/// telegram bot api json of a static sticker with the unique id `unique_id`
+pub(crate) fn tg_sticker_json(unique_id: &str) -> Value {
+	json!({
+		"file_id": format!("file_{unique_id}"),
+		"file_unique_id": unique_id,
+		"width": 512,
+		"height": 512,
+		"is_animated": false,
+		"is_video": false
+	})
+}
+
+/// telegram pack `cats` with stickers of the given unique ids
+pub(crate) fn tg_pack(unique_ids: &[&str]) -> tg::StickerPack {
+	let stickers: Vec<_> = unique_ids.iter().map(|id| tg_sticker_json(id)).collect();
+	let mut pack: tg::StickerPack =
+		serde_json::from_value(json!({ "name": "cats", "title": "Cats", "stickers": stickers })).unwrap();
+	for (i, sticker) in pack.stickers.iter_mut().enumerate() {
+		sticker.pack_name = pack.name.clone();
+		sticker.positon = i;
+	}
+	pack
+}
+
+/// matrix sticker `body` with the url `mxc://example.org/<body>` and optional telegram pack name and unique id
+pub(crate) fn sticker(body: &str, tg: Option<(&str, Option<&str>)>) -> matrix::sticker::Sticker {
+	let tg_sticker = tg.map(|(pack_name, unique_id)| {
+		json!({
+			"pack_name": pack_name,
+			"bot_api_id": null,
+			"bot_api_unique_id": unique_id,
+			"client_api_id": null,
+			"emoji": [],
+			"index": null
+		})
+	});
+	serde_json::from_value(json!({
+		"body": body,
+		"image": {
+			"url": format!("mxc://example.org/{body}"),
+			"meta_data": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" }
+		},
+		"thumbnail": null,
+		"emoticon": null,
+		"emoji": [],
+		"tg_sticker": tg_sticker
+	}))
+	.unwrap()
+}
+
+/// matrix pack `Cats` with a sticker for each entry of `emoji`
+pub(crate) fn matrix_pack(emoji: &[&[&str]]) -> matrix::stickerpack::StickerPack {
+	let stickers: Vec<_> = emoji
+		.iter()
+		.enumerate()
+		.map(|(i, emoji)| {
+			let mut sticker = sticker(&i.to_string(), None);
+			sticker.emoji = emoji.iter().map(|emoji| (*emoji).to_owned()).collect();
+			sticker
+		})
+		.collect();
+	matrix::stickerpack::StickerPack {
+		title: "Cats".to_owned(),
+		id: "tg_name_Cats".to_owned(),
+		tg_pack: None,
+		avatar: None,
+		stickers
+	}
+}
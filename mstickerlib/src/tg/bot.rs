@@ -0,0 +1,89 @@
+use super::{tg_get, tg_post, Config, Sticker, User};
+use crate::error::Error;
+use derive_getters::Getters;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+
+/// Chat of a message; see <https://core.telegram.org/bots/api#chat>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Chat {
+	/// Unique identifier for this chat.
+	id: i64
+}
+
+/// Message sent to the bot; see <https://core.telegram.org/bots/api#message>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Message {
+	/// Unique message identifier inside this chat.
+	message_id: i64,
+	/// Sender of the message; empty for messages sent to channels.
+	from: Option<User>,
+	/// Chat the message belongs to.
+	chat: Chat,
+	/// For text messages, the actual UTF-8 text of the message.
+	text: Option<String>,
+	/// Message is a sticker, information about the sticker.
+	sticker: Option<Sticker>
+}
+
+/// Incoming update; see <https://core.telegram.org/bots/api#update>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct Update {
+	/// The update's unique identifier.
+	/// Updates are confirmed by requesting the next updates with an offset higher than this id.
+	update_id: i64,
+	/// New incoming message; empty for other kinds of updates.
+	message: Option<Message>
+}
+
+#[derive(Serialize)]
+struct GetUpdates {
+	offset: Option<i64>,
+	timeout: u64,
+	/// json array of the update types, which should be received
+	allowed_updates: &'static str
+}
+
+/// Receive incoming messages using long polling; see <https://core.telegram.org/bots/api#getupdates>
+///
+/// Waits up to `timeout` seconds for new messages.
+/// All updates with an id lower than `offset` are confirmed and will not be returned again.
+pub async fn get_updates(tg_config: &Config, offset: Option<i64>, timeout: u64) -> Result<Vec<Update>, Error> {
+	let params = GetUpdates {
+		offset,
+		timeout,
+		allowed_updates: r#"["message"]"#
+	};
+	tg_get(tg_config, "getUpdates", params).await
+}
+
+/// Send a text message to `chat_id`, optional as reply to the message `reply_to`; see <https://core.telegram.org/bots/api#sendmessage>
+pub async fn send_message(tg_config: &Config, chat_id: i64, text: &str, reply_to: Option<i64>) -> Result<(), Error> {
+	let mut form = Form::new().text("chat_id", chat_id.to_string()).text("text", text.to_owned());
+	if let Some(reply_to) = reply_to {
+		form = form.text("reply_to_message_id", reply_to.to_string());
+	}
+	let _: Message = tg_post(tg_config, "sendMessage", form).await?;
+	Ok(())
+}
+
+/// Send a file as document to `chat_id`; see <https://core.telegram.org/bots/api#senddocument>
+pub async fn send_document(
+	tg_config: &Config,
+	chat_id: i64,
+	file_name: &str,
+	data: Vec<u8>,
+	caption: Option<&str>
+) -> Result<(), Error> {
+	let mut form = Form::new()
+		.text("chat_id", chat_id.to_string())
+		.part("document", Part::bytes(data).file_name(file_name.to_owned()));
+	if let Some(caption) = caption {
+		form = form.text("caption", caption.to_owned());
+	}
+	let _: Message = tg_post(tg_config, "sendDocument", form).await?;
+	Ok(())
+}
@@ -0,0 +1,175 @@
+use super::{tg_post, Config, File};
+use crate::{error::Error, image::Image, matrix};
+use futures_util::stream::{self, StreamExt as _};
+use reqwest::multipart::{Form, Part};
+use serde::Serialize;
+use strum_macros::Display;
+
+#[cfg(feature = "log")]
+use log::info;
+
+/// telegram allows at most 50 stickers at the creation of a sticker set
+const MAX_INITIAL_STICKERS: usize = 50;
+/// telegram allows at most 20 emoji per sticker
+const MAX_EMOJI: usize = 20;
+
+/// Format of a telegram sticker; see <https://core.telegram.org/bots/api#inputsticker>
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum StickerFormat {
+	/// png or webp image
+	Static,
+	/// tgs animation
+	Animated,
+	/// webm video
+	Video
+}
+
+/// Sticker, which is added to a sticker set; see <https://core.telegram.org/bots/api#inputsticker>
+#[derive(Clone, Debug, Serialize)]
+pub struct InputSticker {
+	/// file id of the sticker, returned by [upload_sticker_file]
+	pub sticker: String,
+	pub format: StickerFormat,
+	pub emoji_list: Vec<String>
+}
+
+/// Upload a sticker file, to use it at [create_new_sticker_set] or [add_sticker_to_set].
+/// The image must already match the telegram requirements; see [Image::convert_for_telegram].
+/// Return the file id.
+pub async fn upload_sticker_file(
+	tg_config: &Config,
+	user_id: u64,
+	image: &Image,
+	format: StickerFormat
+) -> Result<String, Error> {
+	let part = Part::bytes(image.data.to_vec())
+		.file_name(image.file_name.clone())
+		.mime_str(&image.mime_type()?)?;
+	let form = Form::new()
+		.text("user_id", user_id.to_string())
+		.text("sticker_format", format.to_string())
+		.part("sticker", part);
+	let file: File = tg_post(tg_config, "uploadStickerFile", form).await?;
+	Ok(file.file_id)
+}
+
+/// Create a new sticker set owned by the user `user_id`; see <https://core.telegram.org/bots/api#createnewstickerset>
+///
+/// The name must end with `_by_<bot_username>`.
+pub async fn create_new_sticker_set(
+	tg_config: &Config,
+	user_id: u64,
+	name: &str,
+	title: &str,
+	stickers: &[InputSticker]
+) -> Result<(), Error> {
+	let form = Form::new()
+		.text("user_id", user_id.to_string())
+		.text("name", name.to_owned())
+		.text("title", title.to_owned())
+		.text("stickers", serde_json::to_string(stickers)?);
+	let _: bool = tg_post(tg_config, "createNewStickerSet", form).await?;
+	Ok(())
+}
+
+/// Add a sticker to a set created by the bot; see <https://core.telegram.org/bots/api#addstickertoset>
+pub async fn add_sticker_to_set(tg_config: &Config, user_id: u64, name: &str, sticker: &InputSticker) -> Result<(), Error> {
+	let form = Form::new()
+		.text("user_id", user_id.to_string())
+		.text("name", name.to_owned())
+		.text("sticker", serde_json::to_string(sticker)?);
+	let _: bool = tg_post(tg_config, "addStickerToSet", form).await?;
+	Ok(())
+}
+
+/// download, convert and upload a sticker
+async fn upload_sticker(
+	sticker: &matrix::sticker::Sticker,
+	default_emoji: &str,
+	user_id: u64,
+	tg_config: &Config,
+	matrix_config: &matrix::Config
+) -> Result<InputSticker, Error> {
+	let mut url = sticker.image.url.clone();
+	let data = url.fetch_data(matrix_config).await?;
+	let (_, media_id) = url.parts()?;
+	let extension = sticker.image.meta_data.mimetype.split('/').next_back().unwrap_or("bin");
+	let image = Image {
+		file_name: format!("{media_id}.{extension}"),
+		data,
+		width: sticker.image.meta_data.w,
+		height: sticker.image.meta_data.h
+	};
+	let (image, format) = image.convert_for_telegram().await?;
+	let file_id = upload_sticker_file(tg_config, user_id, &image, format).await?;
+	let mut emoji_list: Vec<String> = sticker
+		.emoji
+		.iter()
+		.filter(|emoji| !emoji.is_empty())
+		.take(MAX_EMOJI)
+		.cloned()
+		.collect();
+	if emoji_list.is_empty() {
+		emoji_list.push(default_emoji.to_owned());
+	}
+	Ok(InputSticker {
+		sticker: file_id,
+		format,
+		emoji_list
+	})
+}
+
+/// Create a new telegram sticker set `name` from a matrix stickerpack.
+///
+/// The stickers are converted to the telegram requirements and owned by the user `user_id`.
+/// Stickers without emoji get `default_emoji`.
+/// If not all stickers could be exported, the errors are returned together with the position of the sticker at the pack.
+/// The position is `None` for the error of the creation of the set.
+pub async fn export_pack(
+	pack: &matrix::stickerpack::StickerPack,
+	name: &str,
+	user_id: u64,
+	default_emoji: &str,
+	tg_config: &Config,
+	matrix_config: &matrix::Config
+) -> Result<(), Vec<(Option<usize>, Error)>> {
+	let results: Vec<_> = stream::iter(pack.stickers.iter())
+		.map(|sticker| upload_sticker(sticker, default_emoji, user_id, tg_config, matrix_config))
+		.buffered(4)
+		.collect()
+		.await;
+	let mut errors = Vec::new();
+	let mut stickers = Vec::new();
+	for (i, result) in results.into_iter().enumerate() {
+		match result {
+			Ok(sticker) => stickers.push((i, sticker)),
+			Err(err) => errors.push((Some(i), err))
+		}
+	}
+	if stickers.is_empty() {
+		return Err(errors);
+	}
+
+	let rest = stickers.split_off(stickers.len().min(MAX_INITIAL_STICKERS));
+	let initial: Vec<_> = stickers.into_iter().map(|(_, sticker)| sticker).collect();
+	#[cfg(feature = "log")]
+	info!("create sticker set {name:?} with {} stickers", initial.len());
+	if let Err(err) = create_new_sticker_set(tg_config, user_id, name, &pack.title, &initial).await {
+		errors.push((None, err));
+		return Err(errors);
+	}
+	for (i, sticker) in rest {
+		#[cfg(feature = "log")]
+		info!("add sticker {i} to sticker set");
+		if let Err(err) = add_sticker_to_set(tg_config, user_id, name, &sticker).await {
+			errors.push((Some(i), err));
+		}
+	}
+	if errors.is_empty() {
+		Ok(())
+	} else {
+		Err(errors)
+	}
+}
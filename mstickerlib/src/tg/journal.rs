@@ -0,0 +1,153 @@
+use super::StickerPack;
+use crate::matrix;
+use std::{
+	collections::HashMap,
+	io,
+	path::{Path, PathBuf}
+};
+use tokio::{
+	fs::{self, File},
+	io::AsyncWriteExt as _,
+	sync::Mutex
+};
+
+#[cfg(feature = "log")]
+use log::warn;
+
+/// Journal of a pack import, which allows resuming an interrupted import.
+///
+/// Every successfully imported sticker is appended to the journal file.
+/// If the same pack is imported again with this journal, these stickers are not imported again.
+/// Use one journal per pack and remove it with [ImportJournal::remove] after the import was completed.
+/// Keep in mind that stickers loaded from the journal do not have cached data.
+pub struct ImportJournal {
+	path: PathBuf,
+	stickers: Vec<matrix::sticker::Sticker>,
+	file: Mutex<File>
+}
+
+impl ImportJournal {
+	/// Open the journal at `path` or create a new one, if it does not exist.
+	pub async fn open<P>(path: P) -> io::Result<Self>
+	where
+		P: AsRef<Path>
+	{
+		let path = path.as_ref();
+		let mut stickers = Vec::new();
+		let data = match fs::read(path).await {
+			Ok(data) => data,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(error) => return Err(error)
+		};
+		for (i, line) in data.split(|&byte| byte == b'\n').enumerate() {
+			if line.is_empty() {
+				continue;
+			}
+			match serde_json::from_slice(line) {
+				Ok(sticker) => stickers.push(sticker),
+				// the last line can be incomplete, if the import was killed
+				Err(error) => {
+					#[cfg(feature = "log")]
+					warn!("Line {} of journal({}) can not be read: {:?}", i + 1, path.display(), error);
+					#[cfg(not(feature = "log"))]
+					let _ = (i, error); //fix unused warning
+				}
+			}
+		}
+		let mut file = fs::OpenOptions::new().append(true).create(true).open(path).await?;
+		// terminate an incomplete last line, so new stickers are not appended to it
+		if data.last().is_some_and(|&byte| byte != b'\n') {
+			file.write_all(b"\n").await?;
+			file.flush().await?;
+		}
+		Ok(Self {
+			path: path.to_owned(),
+			stickers,
+			file: Mutex::new(file)
+		})
+	}
+
+	/// Stickers of `pack`, which were already imported, by their position.
+	/// Stickers, which are not part of the pack anymore, are ignored.
+	pub(crate) fn imported(&self, pack: &StickerPack) -> HashMap<usize, matrix::sticker::Sticker> {
+		self.stickers
+			.iter()
+			.filter_map(|sticker| {
+				let tg_sticker = sticker.tg_sticker.as_ref()?;
+				let position = tg_sticker.index?;
+				let unique_id = tg_sticker.bot_api_unique_id.as_deref()?;
+				(tg_sticker.pack_name == pack.name
+					&& pack
+						.stickers
+						.get(position)
+						.is_some_and(|tg_sticker| tg_sticker.image().file_unique_id == unique_id))
+				.then(|| (position, sticker.clone()))
+			})
+			.collect()
+	}
+
+	pub(crate) async fn add(&self, sticker: &matrix::sticker::Sticker) -> io::Result<()> {
+		let mut line = serde_json::to_vec(sticker)?;
+		line.push(b'\n');
+		let mut file = self.file.lock().await;
+		file.write_all(&line).await?;
+		file.flush().await
+	}
+
+	/// Delete the journal file.
+	pub async fn remove(self) -> io::Result<()> {
+		drop(self.file);
+		fs::remove_file(&self.path).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ImportJournal;
+	use crate::test_util::{sticker, tg_pack};
+
+	/// journal line of a sticker of pack `pack_name` at `index`
+	fn line(pack_name: &str, unique_id: &str, index: usize) -> String {
+		let mut sticker = sticker(unique_id, Some((pack_name, Some(unique_id))));
+		sticker.tg_sticker.as_mut().unwrap().index = Some(index);
+		serde_json::to_string(&sticker).unwrap()
+	}
+
+	#[tokio::test]
+	async fn resume() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("cats.jsonl");
+		let mut lines = [
+			line("cats", "a", 0),
+			// sticker has moved to another position
+			line("cats", "b", 1),
+			line("dogs", "c", 2),
+			line("cats", "d", 3)
+		]
+		.join("\n");
+		// the import was killed while writing the last line
+		lines.push('\n');
+		lines.push_str(&line("cats", "e", 4)[..20]);
+		std::fs::write(&path, lines).unwrap();
+
+		let journal = ImportJournal::open(&path).await.unwrap();
+		assert_eq!(journal.stickers.len(), 4);
+		let pack = tg_pack(&["a", "x", "b", "d", "e"]);
+		let mut imported: Vec<_> = journal.imported(&pack).into_iter().collect();
+		imported.sort_by_key(|(position, _)| *position);
+		let imported: Vec<_> = imported
+			.iter()
+			.map(|(position, sticker)| (*position, sticker.body.as_str()))
+			.collect();
+		assert_eq!(imported, [(0, "a"), (3, "d")]);
+
+		// new stickers are appended after the incomplete line
+		let sticker = serde_json::from_str(&line("cats", "e", 4)).unwrap();
+		journal.add(&sticker).await.unwrap();
+		drop(journal);
+		let journal = ImportJournal::open(&path).await.unwrap();
+		assert_eq!(journal.imported(&pack).len(), 3);
+		journal.remove().await.unwrap();
+		assert!(!path.exists());
+	}
+}
@@ -4,14 +4,29 @@ use crate::{
 	image::AnimationFormat,
 	CLIENT
 };
+use derive_getters::Getters;
 use monostate::MustBe;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+mod bot;
+pub use bot::{get_updates, send_document, send_message, Chat, Message, Update};
+
 mod sticker;
-pub use sticker::{PhotoSize, Sticker};
+pub use sticker::{MaskPoint, MaskPosition, PhotoSize, Sticker, StickerType};
+
+mod export;
+pub use export::{
+	add_sticker_to_set, create_new_sticker_set, export_pack, upload_sticker_file, InputSticker, StickerFormat
+};
+
+mod journal;
+pub use journal::ImportJournal;
+
+mod pack_ref;
+pub use pack_ref::{pack_url_to_name, PackKind, PackRef};
 
 mod stickerpack;
-pub use stickerpack::{pack_url_to_name, StickerPack};
+pub use stickerpack::{StickerPack, UpdateDiff};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
@@ -22,7 +37,7 @@ pub struct Config {
 #[non_exhaustive]
 pub struct ImportConfig<'a, D = crate::database::DummyDatabase>
 where
-	D: Database
+	D: Database + ?Sized
 {
 	/// animaton format, to which animated sticker will be converted.
 	/// If `None` original format will be used, this is propably not supported by matrix cilents.
@@ -30,6 +45,9 @@ where
 	/// database to track, which files was already uploaded,
 	/// to aviod duplicaded uploads of the same file
 	pub database: Option<&'a D>,
+	/// journal to resume an interrupted import of a pack.
+	/// Is ignored at a dry run.
+	pub journal: Option<&'a ImportJournal>,
 	/// Do not upload anythink to matirx.
 	/// **WARNING:** the generate stickerpack will not have valid matrix urls.
 	/// Use this function only for testing and to prevent your homesever from being spammed with files while testing.
@@ -40,28 +58,65 @@ where
 	/// Do not convert animated sticker and keep lootie files.
 	/// Animated sticker will be still unpack (they are zstd compressed lottie files).
 	/// Import of animated stickers fail, if set to `false` and `lottie` features is dissable.
-	pub keep_lottie: bool
+	pub keep_lottie: bool,
+	/// RGB color for custom emoji, which are repainted to the text color by telegram clients.
+	/// If `None` the original color is kept.
+	pub repaint_color: Option<[u8; 3]>,
+	/// Also import the full-screen effect of premium stickers.
+	pub premium_animations: bool
 }
 
 impl<D> Default for ImportConfig<'_, D>
 where
-	D: Database
+	D: Database + ?Sized
 {
 	fn default() -> Self {
 		Self {
 			animation_format: AnimationFormat::Webp,
 			database: None,
+			journal: None,
 			dry_run: false,
 			keep_webm: false,
-			keep_lottie: false
+			keep_lottie: false,
+			repaint_color: None,
+			premium_animations: false
 		}
 	}
 }
 
+/// Telegram user or bot; see <https://core.telegram.org/bots/api#user>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct User {
+	/// Unique identifier for this user or bot.
+	id: u64,
+	/// True, if this user is a bot.
+	is_bot: bool,
+	/// User's or bot's first name.
+	first_name: String,
+	/// User's or bot's username.
+	username: Option<String>
+}
+
+/// Get the user of the bot.
+/// Can be used to check if the bot key is valid.
+pub async fn get_me(tg_config: &Config) -> Result<User, Error> {
+	tg_get(tg_config, "getMe", ()).await
+}
+
 /// File storage at Telegram; see <https://core.telegram.org/bots/api#file>
-#[derive(Debug, Deserialize)]
-struct File {
-	file_path: String
+#[derive(Clone, Debug, Deserialize, Getters, Hash)]
+#[non_exhaustive]
+pub struct File {
+	/// Identifier for this file, which can be used to download or reuse the file.
+	file_id: String,
+	/// Unique identifier for this file, which is supposed to be the same over time and
+	/// for different bots. Can't be used to download or reuse the file.
+	file_unique_id: String,
+	/// File size in bytes.
+	file_size: Option<u64>,
+	/// Path to download the file; only set by `getFile`.
+	file_path: Option<String>
 }
 
 #[derive(Deserialize)]
@@ -82,6 +137,17 @@ enum TgResponse<T> {
 	}
 }
 
+impl<T> TgResponse<T> {
+	fn into_result(self) -> Result<T, Error> {
+		match self {
+			TgResponse::Ok { result, .. } => Ok(result),
+			TgResponse::Err {
+				error_code, description, ..
+			} => Err(Error::Telegram(TelgramApiError { error_code, description }))
+		}
+	}
+}
+
 async fn tg_get<T, P>(tg_config: &Config, operation: &str, params: P) -> Result<T, Error>
 where
 	T: DeserializeOwned,
@@ -96,11 +162,20 @@ where
 		.await?
 		.json()
 		.await?;
-	let result = match resp {
-		TgResponse::Ok { result, .. } => result,
-		TgResponse::Err {
-			error_code, description, ..
-		} => return Err(Error::Telegram(TelgramApiError { error_code, description }))
-	};
-	Ok(result)
+	resp.into_result()
+}
+
+async fn tg_post<T>(tg_config: &Config, operation: &str, form: reqwest::multipart::Form) -> Result<T, Error>
+where
+	T: DeserializeOwned
+{
+	let resp: TgResponse<T> = CLIENT
+		.get()
+		.post(format!("https://api.telegram.org/bot{}/{}", tg_config.bot_key, operation))
+		.multipart(form)
+		.send()
+		.await?
+		.json()
+		.await?;
+	resp.into_result()
 }
@@ -0,0 +1,172 @@
+use crate::error::InvalidPackUrl;
+use derive_getters::Getters;
+use std::{
+	fmt::{self, Display},
+	str::FromStr
+};
+
+/// maximum length of a pack name
+const MAX_NAME_LEN: usize = 64;
+
+/// Kind of a telegram pack
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PackKind {
+	/// sticker pack, shared by `t.me/addstickers/…` links
+	Stickers,
+	/// custom emoji set, shared by `t.me/addemoji/…` links
+	Emoji
+}
+
+impl PackKind {
+	fn path(self) -> &'static str {
+		match self {
+			Self::Stickers => "addstickers",
+			Self::Emoji => "addemoji"
+		}
+	}
+}
+
+/// Reference to a telegram sticker pack or custom emoji set.
+///
+/// Can be parsed from a link like `https://t.me/addstickers/<name>`, `https://telegram.me/addemoji/<name>`
+/// or `tg://addstickers?set=<name>` and from a bare pack name.
+/// Bare names are treated as sticker packs, since the kind can not be known without asking telegram.
+#[derive(Clone, Debug, Eq, Getters, Hash, PartialEq)]
+pub struct PackRef {
+	name: String,
+	kind: PackKind
+}
+
+/// Check the name against the telegram naming rules:
+/// only english letters, digits and underscores, starting with a letter.
+fn is_valid_name(name: &str) -> bool {
+	name.len() <= MAX_NAME_LEN
+		&& name.starts_with(|c: char| c.is_ascii_alphabetic())
+		&& name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// parse a pack link or name; return the name and the kind of the pack
+fn parse(url: &str) -> Option<(&str, PackKind)> {
+	let url = url.trim();
+	let (name, kind) = if let Some(query) = url.strip_prefix("tg://") {
+		let (path, query) = query.split_once('?')?;
+		let kind = [PackKind::Stickers, PackKind::Emoji]
+			.into_iter()
+			.find(|kind| path.trim_end_matches('/') == kind.path())?;
+		let name = query.split('&').find_map(|param| param.strip_prefix("set="))?;
+		(name, kind)
+	} else if url.contains(['/', ':', '.']) {
+		let url = url
+			.strip_prefix("https://")
+			.or_else(|| url.strip_prefix("http://"))
+			.unwrap_or(url);
+		let url = url.strip_prefix("www.").unwrap_or(url);
+		let path = url.strip_prefix("t.me/").or_else(|| url.strip_prefix("telegram.me/"))?;
+		// remove query, fragment and trailing slashes
+		let path = path.split(['?', '#']).next().unwrap_or_default().trim_end_matches('/');
+		let (kind, name) = path.split_once('/')?;
+		let kind = [PackKind::Stickers, PackKind::Emoji]
+			.into_iter()
+			.find(|other| kind == other.path())?;
+		(name, kind)
+	} else {
+		(url, PackKind::Stickers)
+	};
+	is_valid_name(name).then_some((name, kind))
+}
+
+impl PackRef {
+	/// Find all pack links at a text; each pack is only returned once.
+	/// Words without `/` or `:` are ignored, to not confuse normal words with pack names.
+	pub fn find_links(text: &str) -> Vec<Self> {
+		let mut packs: Vec<Self> = Vec::new();
+		for pack in text
+			.split_whitespace()
+			// punctuation around the link, like at `(t.me/addstickers/Cats),`
+			.map(|word| {
+				word.trim_start_matches(['(', '<', '"', '\''])
+					.trim_end_matches([',', '.', ';', '!', '?', ')', '>', '"', '\''])
+			})
+			.filter(|word| word.contains(['/', ':']))
+			.filter_map(|word| word.parse::<Self>().ok())
+		{
+			if !packs.iter().any(|other| other.name == pack.name) {
+				packs.push(pack);
+			}
+		}
+		packs
+	}
+}
+
+impl FromStr for PackRef {
+	type Err = InvalidPackUrl;
+
+	fn from_str(url: &str) -> Result<Self, Self::Err> {
+		let (name, kind) = parse(url).ok_or_else(|| InvalidPackUrl(url.to_owned()))?;
+		Ok(Self {
+			name: name.to_owned(),
+			kind
+		})
+	}
+}
+
+/// format the reference as `https://t.me/…` link
+impl Display for PackRef {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "https://t.me/{}/{}", self.kind.path(), self.name)
+	}
+}
+
+/// Convert telegram stickerpack or custom emoji set url to pack name.
+///
+/// Accepts all links and names supported by [PackRef].
+pub fn pack_url_to_name(url: &str) -> Result<&str, InvalidPackUrl> {
+	parse(url).map(|(name, _)| name).ok_or_else(|| InvalidPackUrl(url.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{pack_url_to_name, PackKind, PackRef};
+
+	#[test]
+	fn pack_url() {
+		assert_eq!(pack_url_to_name("https://t.me/addstickers/cats").unwrap(), "cats");
+		assert_eq!(pack_url_to_name("tg://addemoji?set=cats").unwrap(), "cats");
+		assert_eq!(pack_url_to_name("t.me/addemoji/cats").unwrap(), "cats");
+		assert!(pack_url_to_name("https://t.me/cats").is_err());
+	}
+
+	#[test]
+	fn pack_ref() {
+		let parse = |url: &str| url.parse::<PackRef>().map(|pack| (pack.name, pack.kind)).ok();
+		let stickers = Some(("Cats_2".to_owned(), PackKind::Stickers));
+		let emoji = Some(("Cats_2".to_owned(), PackKind::Emoji));
+		assert_eq!(parse("https://telegram.me/addstickers/Cats_2"), stickers);
+		assert_eq!(parse("http://www.t.me/addstickers/Cats_2/"), stickers);
+		assert_eq!(parse("t.me/addstickers/Cats_2?foo=bar"), stickers);
+		assert_eq!(parse(" Cats_2 "), stickers);
+		assert_eq!(parse("tg://addemoji?set=Cats_2&foo"), emoji);
+		assert_eq!(parse("https://t.me/addemoji/Cats_2#top"), emoji);
+		assert_eq!(parse("https://t.me/addstickers/"), None);
+		assert_eq!(parse("https://example.org/addstickers/Cats_2"), None);
+		assert_eq!(parse("2cats"), None);
+		assert_eq!(parse("cats-and-dogs"), None);
+		assert_eq!(parse(&"a".repeat(65)), None);
+		assert_eq!(
+			"tg://addemoji?set=Cats_2".parse::<PackRef>().unwrap().to_string(),
+			"https://t.me/addemoji/Cats_2"
+		);
+	}
+
+	#[test]
+	fn find_links() {
+		let names = |text: &str| -> Vec<String> { PackRef::find_links(text).into_iter().map(|pack| pack.name).collect() };
+		assert_eq!(
+			names("look at https://t.me/addstickers/Cats and\nt.me/addemoji/Dogs, https://t.me/addstickers/Cats"),
+			["Cats", "Dogs"]
+		);
+		assert_eq!(names("(https://t.me/addstickers/Cats/)."), ["Cats"]);
+		assert!(names("Cats and Dogs").is_empty());
+		assert!(names("https://example.org/addstickers/Cats").is_empty());
+	}
+}
@@ -1,10 +1,13 @@
-use std::sync::Arc;
+use std::{
+	hash::{Hash, Hasher},
+	sync::Arc
+};
 
-use super::ImportConfig;
+use super::{File, ImportConfig};
 use crate::{
 	error::Error,
-	image::Image,
-	matrix::{self, sticker_formats::ponies, Mxc},
+	image::{sniff_format, Image},
+	matrix::{self, sticker_formats::ponies::Usage},
 	CLIENT
 };
 use derive_getters::Getters;
@@ -13,6 +16,88 @@ use serde::Deserialize;
 #[cfg(feature = "log")]
 use log::{info, warn};
 
+/// size of custom emoji
+const EMOJI_SIZE: u32 = 100;
+
+/// Type of a sticker or stickerpack; see <https://core.telegram.org/bots/api#sticker>
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StickerType {
+	#[default]
+	Regular,
+	Mask,
+	/// custom emoji, which can be used inside messages
+	CustomEmoji
+}
+
+/// Part of the face relative to which a mask should be placed; see <https://core.telegram.org/bots/api#maskposition>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskPoint {
+	Forehead,
+	Eyes,
+	Mouth,
+	Chin
+}
+
+/// Position on faces where a mask should be placed by default; see <https://core.telegram.org/bots/api#maskposition>
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[non_exhaustive]
+pub struct MaskPosition {
+	/// The part of the face relative to which the mask should be placed.
+	point: MaskPoint,
+	/// Shift by X-axis measured in widths of the mask scaled to the face size, from left to right.
+	x_shift: f64,
+	/// Shift by Y-axis measured in heights of the mask scaled to the face size, from top to bottom.
+	y_shift: f64,
+	/// Mask scaling coefficient.
+	scale: f64
+}
+
+impl Hash for MaskPosition {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.point.hash(state);
+		self.x_shift.to_bits().hash(state);
+		self.y_shift.to_bits().hash(state);
+		self.scale.to_bits().hash(state);
+	}
+}
+
+/// download a file; return its path at telegram and its data
+async fn download(tg_config: &super::Config, file_id: &str) -> Result<(String, Vec<u8>), Error> {
+	let file: File = super::tg_get(tg_config, "getFile", [("file_id", file_id)]).await?;
+	let file_path = file.file_path.ok_or_else(|| Error::NoFilePath(file_id.to_owned()))?;
+	let data = CLIENT
+		.get()
+		.get(format!(
+			"https://api.telegram.org/file/bot{}/{}",
+			tg_config.bot_key, file_path
+		))
+		.send()
+		.await?
+		.bytes()
+		.await?
+		.to_vec();
+	Ok((file_path, data))
+}
+
+impl File {
+	/// Download the file.
+	/// The size of the image is detected by its content, since telegram does not provide it.
+	pub async fn download(&self, tg_config: &super::Config) -> Result<Image, Error> {
+		let (file_path, data) = download(tg_config, &self.file_id).await?;
+		let (width, height) = sniff_format(&data)
+			.map(|(_, width, height)| (width, height))
+			.unwrap_or_default();
+		Ok(Image {
+			data: Arc::new(data),
+			file_name: file_path,
+			width,
+			height
+		})
+	}
+}
+
 ///see <https://core.telegram.org/bots/api#photosize>
 #[derive(Clone, Debug, Deserialize, Hash)]
 #[non_exhaustive]
@@ -25,26 +110,17 @@ pub struct PhotoSize {
 	/// Sticker width
 	pub width: u32,
 	/// Sticker height
-	pub height: u32
+	pub height: u32,
+	/// File size in bytes
+	pub file_size: Option<u64>
 }
 impl PhotoSize {
 	/// download the image of the PhotoSize
 	pub async fn download(&self, tg_config: &super::Config) -> Result<Image, Error> {
-		let file: super::File = super::tg_get(tg_config, "getFile", [("file_id", &self.file_id)]).await?;
-		let data = CLIENT
-			.get()
-			.get(format!(
-				"https://api.telegram.org/file/bot{}/{}",
-				tg_config.bot_key, file.file_path
-			))
-			.send()
-			.await?
-			.bytes()
-			.await?
-			.to_vec();
+		let (file_path, data) = download(tg_config, &self.file_id).await?;
 		Ok(Image {
 			data: Arc::new(data),
-			file_name: file.file_path,
+			file_name: file_path,
 			width: self.width,
 			height: self.height
 		})
@@ -58,10 +134,11 @@ impl PhotoSize {
 		pack_name: &str,
 		positon: usize,
 		emoji: Option<&str>,
-		thumb: bool
+		thumb: bool,
+		repaint: bool
 	) -> Result<matrix::sticker::Image, Error>
 	where
-		D: crate::database::Database
+		D: crate::database::Database + ?Sized
 	{
 		#[cfg(not(feature = "log"))]
 		{
@@ -78,47 +155,13 @@ impl PhotoSize {
 		#[cfg(feature = "log")]
 		info!("download sticker {pack_name}:{positon:03} {emoji:<2} {thumb}");
 		// download and convert sticker from telegram
-		let mut image = self.download(tg_config).await?;
-		image = image.unpack_tgs().await?;
-		if image.file_name.ends_with(".lottie") && !advance_config.keep_lottie {
-			// file extension is now checked double.
-			// Here and inside `convert_...`
-			// But `convert_...` function does not exist, if feature is dissable.
-			#[cfg(feature = "lottie")]
-			{
-				image = image.convert_lottie(advance_config.animation_format).await?;
-			}
-			#[cfg(not(feature = "lottie"))]
-			return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Lottie));
-		}
-		if image.file_name.ends_with(".webm") && !advance_config.keep_webm {
-			#[cfg(feature = "ffmpeg")]
-			{
-				image = image.convert_webm2webp().await?;
-			}
-			#[cfg(not(feature = "ffmpeg"))]
-			return Err(Error::UnsupportedFormat(crate::error::UnsupportedFormat::Webm));
+		let mut image = self.download(tg_config).await?.convert(advance_config).await?;
+		if let Some(color) = advance_config.repaint_color.filter(|_| repaint) {
+			image = image.repaint(color).await?;
 		}
 		#[cfg(feature = "log")]
 		info!("  upload sticker {pack_name}:{positon:03} {emoji:<2} {thumb}");
-		let mxc = if advance_config.dry_run {
-			#[cfg(feature = "log")]
-			{
-				warn!("  upload skipped; dryrun");
-			}
-			Mxc::new("!!! DRY_RUN !!!".to_owned(), Some(image.data.clone())) //cloning Arc is cheap
-		} else {
-			let (mxc, has_uploded) = image.upload(matrix_config, advance_config.database).await?;
-			#[cfg(feature = "log")]
-			if !has_uploded {
-				info!("  upload skipped; file with this hash was already uploaded");
-			}
-			#[cfg(not(feature = "log"))]
-			let _ = has_uploded; //fix unused warning
-			mxc
-		};
-		let meta_data = ponies::MetaData::try_from(image)?;
-		Ok(matrix::sticker::Image { url: mxc, meta_data })
+		image.import(matrix_config, advance_config).await
 	}
 }
 
@@ -139,7 +182,21 @@ pub struct Sticker {
 	/// True if the sticker is [animated](https://telegram.org/blog/animated-stickers).
 	is_animated: bool,
 	/// True if the sticker is a [video sticker](https://telegram.org/blog/video-stickers-better-reactions).
-	is_video: bool
+	is_video: bool,
+	/// Type of the sticker.
+	#[serde(rename = "type", default)]
+	sticker_type: StickerType,
+	/// Name of the sticker set to which the sticker belongs.
+	set_name: Option<String>,
+	/// For premium regular stickers, premium animation for the sticker.
+	premium_animation: Option<File>,
+	/// For mask stickers, the position where the mask should be placed.
+	mask_position: Option<MaskPosition>,
+	/// For custom emoji, unique identifier of the custom emoji.
+	custom_emoji_id: Option<String>,
+	/// True, if the custom emoji must be repainted to the text color in messages.
+	#[serde(default)]
+	needs_repainting: bool
 }
 
 impl Sticker {
@@ -151,7 +208,7 @@ impl Sticker {
 		advance_config: &ImportConfig<'a, D>
 	) -> Result<crate::matrix::sticker::Sticker, Error>
 	where
-		D: crate::database::Database
+		D: crate::database::Database + ?Sized
 	{
 		// download sticker from telegram
 		let image = self
@@ -163,7 +220,8 @@ impl Sticker {
 				&self.pack_name,
 				self.positon,
 				self.emoji.as_deref(),
-				false
+				false,
+				self.needs_repainting
 			)
 			.await?;
 		let thumb = match self.thumbnail.as_ref() {
@@ -177,28 +235,41 @@ impl Sticker {
 						&self.pack_name,
 						self.positon,
 						self.emoji.as_deref(),
-						true
+						true,
+						self.needs_repainting
 					)
 					.await?
 			)
 		};
 
+		let premium_animation = self.import_premium_animation(tg_config, matrix_config, advance_config).await;
+
 		//construct Sticker Struct
 		let tg_info = matrix::sticker::TgStickerInfo {
 			bot_api_id: Some(self.image.file_id.clone()),
+			bot_api_unique_id: Some(self.image.file_unique_id.clone()),
 			client_api_id: None,
 			emoji: self.emoji.clone().into_iter().collect(),
 			pack_name: self.pack_name.clone(),
 			index: Some(self.positon)
 		};
-		let sticker = matrix::sticker::Sticker {
+		let mut sticker = matrix::sticker::Sticker {
 			body: self.emoji.clone().unwrap_or_default(),
 			image,
 			thumbnail: thumb,
+			premium_animation,
 			emoji: self.emoji.clone().into_iter().collect(),
 			emoticon: None,
+			usage: [Usage::Sticker].into_iter().collect(),
 			tg_sticker: Some(tg_info)
 		};
+		if self.sticker_type == StickerType::CustomEmoji {
+			// custom emoji have no name, so the shortcode is generated from pack and position
+			sticker.emoticon = Some(format!("{}_{}", self.pack_name, self.positon + 1));
+			sticker.usage = [Usage::Emoticon].into_iter().collect();
+			sticker.image.meta_data.w = EMOJI_SIZE;
+			sticker.image.meta_data.h = EMOJI_SIZE;
+		}
 
 		#[cfg(feature = "log")]
 		info!(
@@ -209,4 +280,105 @@ impl Sticker {
 		);
 		Ok(sticker)
 	}
+
+	/// Import the full-screen effect of a premium sticker, if [ImportConfig::premium_animations] is set.
+	async fn import_premium_animation<'a, D>(
+		&self,
+		tg_config: &super::Config,
+		matrix_config: &crate::matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Option<matrix::sticker::Image>
+	where
+		D: crate::database::Database + ?Sized
+	{
+		let file = self
+			.premium_animation
+			.as_ref()
+			.filter(|_| advance_config.premium_animations)?;
+		#[cfg(feature = "log")]
+		info!(
+			"download premium animation {}:{:03} {}",
+			self.pack_name,
+			self.positon,
+			self.emoji.as_deref().unwrap_or_default()
+		);
+		let animation = async {
+			file.download(tg_config)
+				.await?
+				.convert(advance_config)
+				.await?
+				.import(matrix_config, advance_config)
+				.await
+		};
+		self.skip_failed_premium_animation(animation.await)
+	}
+
+	/// The sticker can be used without its premium animation, so a failed import does only drop the animation.
+	fn skip_failed_premium_animation(
+		&self,
+		animation: Result<matrix::sticker::Image, Error>
+	) -> Option<matrix::sticker::Image> {
+		match animation {
+			Ok(animation) => Some(animation),
+			Err(err) => {
+				#[cfg(feature = "log")]
+				warn!(
+					"failed to import premium animation of {}:{:03}, import sticker without it: {err}",
+					self.pack_name, self.positon
+				);
+				#[cfg(not(feature = "log"))]
+				let _ = err; //fix unused warning
+				None
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Sticker;
+	use crate::{
+		database::DummyDatabase,
+		error::Error,
+		test_util::{configs, tg_sticker_json},
+		tg::ImportConfig
+	};
+
+	fn premium_sticker() -> Sticker {
+		let mut sticker = tg_sticker_json("uid");
+		sticker["premium_animation"] = serde_json::json!({ "file_id": "premium", "file_unique_id": "upremium" });
+		serde_json::from_value(sticker).unwrap()
+	}
+
+	#[test]
+	fn failed_premium_animation() {
+		let sticker = premium_sticker();
+		let image: crate::matrix::sticker::Image = serde_json::from_value(serde_json::json!({
+			"url": "mxc://example.org/premium",
+			"meta_data": { "w": 512, "h": 512, "size": 1, "mimetype": "image/webp" }
+		}))
+		.unwrap();
+		assert_eq!(
+			sticker.skip_failed_premium_animation(Ok(image)).unwrap().url.url(),
+			"mxc://example.org/premium"
+		);
+		let err = Error::NoFilePath("premium".to_owned());
+		assert!(sticker.skip_failed_premium_animation(Err(err)).is_none());
+	}
+
+	#[tokio::test]
+	async fn premium_animation_disabled() {
+		let (matrix_config, tg_config) = configs();
+		let import_config = ImportConfig::<DummyDatabase> {
+			database: None,
+			dry_run: true,
+			..Default::default()
+		};
+		// nothing is downloaded if premium animations are not imported
+		assert!(!import_config.premium_animations);
+		assert!(premium_sticker()
+			.import_premium_animation(&tg_config, &matrix_config, &import_config)
+			.await
+			.is_none());
+	}
 }
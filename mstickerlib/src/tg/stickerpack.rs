@@ -1,12 +1,12 @@
-use super::{sticker::Sticker, tg_get, Config, ImportConfig};
-use crate::{
-	database::Database,
-	error::{Error, InvalidPackUrl},
-	matrix
+use super::{
+	sticker::{PhotoSize, Sticker, StickerType},
+	tg_get, Config, ImportConfig
 };
+use crate::{database::Database, error::Error, matrix};
 use derive_getters::Getters;
 use futures_util::future::join_all;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[cfg(feature = "log")]
 use log::{info, warn};
@@ -16,7 +16,12 @@ use log::{info, warn};
 pub struct StickerPack {
 	pub(crate) name: String,
 	pub(crate) title: String,
-	pub(crate) stickers: Vec<Sticker>
+	/// type of all stickers of the pack
+	#[serde(default)]
+	pub(crate) sticker_type: StickerType,
+	pub(crate) stickers: Vec<Sticker>,
+	/// thumbnail of the pack in webp, tgs or webm format
+	pub(crate) thumbnail: Option<PhotoSize>
 }
 
 impl StickerPack {
@@ -44,7 +49,7 @@ impl StickerPack {
 		advance_config: &ImportConfig<'a, D>
 	) -> Result<matrix::stickerpack::StickerPack, (matrix::stickerpack::StickerPack, Vec<(usize, Error)>)>
 	where
-		D: Database
+		D: Database + ?Sized
 	{
 		#[cfg(feature = "log")]
 		if log::log_enabled!(log::Level::Info) {
@@ -56,65 +61,370 @@ impl StickerPack {
 			);
 		}
 
-		let stickers_import_futures = self
-			.stickers
-			.iter()
-			.map(|f| f.import(tg_config, matrix_config, advance_config));
+		let (ok_stickers, err_stickers) = self
+			.import_stickers(self.stickers.iter(), tg_config, matrix_config, advance_config)
+			.await;
+		let avatar = self
+			.import_avatar(&ok_stickers, tg_config, matrix_config, advance_config)
+			.await;
+		match self.finish_import(ok_stickers, avatar, err_stickers) {
+			(stickerpack, err_stickers) if err_stickers.is_empty() => Ok(stickerpack),
+			err => Err(err)
+		}
+	}
+
+	/// Retry the import of the failed stickers of a previous partial import.
+	///
+	/// `pack` is the successful part and `failed` are the positions of the failed stickers,
+	/// like they are returned by [StickerPack::import].
+	pub async fn retry_failed<'a, D, I>(
+		&self,
+		pack: matrix::stickerpack::StickerPack,
+		failed: I,
+		tg_config: &Config,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Result<matrix::stickerpack::StickerPack, (matrix::stickerpack::StickerPack, Vec<(usize, Error)>)>
+	where
+		D: Database + ?Sized,
+		I: IntoIterator<Item = usize>
+	{
+		let failed = failed.into_iter().filter_map(|position| self.stickers.get(position));
+		let (mut ok_stickers, err_stickers) = self.import_stickers(failed, tg_config, matrix_config, advance_config).await;
+		ok_stickers.extend(pack.stickers);
+		ok_stickers.sort_by_key(|sticker| sticker.tg_sticker.as_ref().and_then(|tg_sticker| tg_sticker.index));
+		let avatar = match pack.avatar {
+			Some(avatar) => Some(avatar),
+			None => {
+				self.import_avatar(&ok_stickers, tg_config, matrix_config, advance_config)
+					.await
+			},
+		};
+		match self.finish_import(ok_stickers, avatar, err_stickers) {
+			(stickerpack, err_stickers) if err_stickers.is_empty() => Ok(stickerpack),
+			err => Err(err)
+		}
+	}
+
+	/// Import the thumbnail of the pack as avatar.
+	/// If the pack has no thumbnail or its import fails, the thumbnail of the first sticker is used.
+	async fn import_avatar<'a, D>(
+		&self,
+		stickers: &[matrix::sticker::Sticker],
+		tg_config: &Config,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Option<matrix::sticker::Image>
+	where
+		D: Database + ?Sized
+	{
+		if let Some(thumbnail) = &self.thumbnail {
+			#[cfg(feature = "log")]
+			info!("download thumbnail of pack {}", self.name);
+			let avatar = async {
+				thumbnail
+					.download(tg_config)
+					.await?
+					.convert(advance_config)
+					.await?
+					.import(matrix_config, advance_config)
+					.await
+			};
+			match avatar.await {
+				Ok(avatar) => return Some(avatar),
+				Err(err) => {
+					#[cfg(feature = "log")]
+					warn!(
+						"failed to import thumbnail of pack {}, use first sticker instead: {err}",
+						self.name
+					);
+					#[cfg(not(feature = "log"))]
+					let _ = err; //fix unused warning
+				}
+			}
+		}
+		fallback_avatar(stickers)
+	}
+
+	/// Check if the thumbnail of the pack differs from the one the avatar of the old pack was imported from.
+	/// Packs, which have not stored the thumbnail, are treated as changed if this pack has a thumbnail.
+	fn thumbnail_changed(&self, old: Option<&matrix::stickerpack::TgPackInfo>) -> bool {
+		self.thumbnail.as_ref().map(|thumbnail| thumbnail.file_unique_id.as_str())
+			!= old.and_then(|old| old.thumbnail_unique_id.as_deref())
+	}
+
+	/// Import the given stickers of this pack.
+	/// Stickers, which are already part of the journal, are taken from there
+	/// and new imported stickers are added to the journal.
+	async fn import_stickers<'a, 'b, D, I>(
+		&self,
+		stickers: I,
+		tg_config: &Config,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> (Vec<matrix::sticker::Sticker>, Vec<(usize, Error)>)
+	where
+		D: Database + ?Sized,
+		I: Iterator<Item = &'b Sticker>
+	{
+		// the journal must not contain the invalid urls of a dry run
+		let journal = advance_config.journal.filter(|_| !advance_config.dry_run);
+		let imported = journal.map(|journal| journal.imported(self)).unwrap_or_default();
+		#[cfg(feature = "log")]
+		if !imported.is_empty() {
+			info!(
+				"resume import of {}, {} stickers were already imported",
+				self.name,
+				imported.len()
+			);
+		}
+		let imported = &imported;
+
+		let stickers_import_futures = stickers.map(|sticker| async move {
+			if let Some(value) = imported.get(&sticker.positon) {
+				return (sticker.positon, Ok(value.clone()));
+			}
+			let result = sticker.import(tg_config, matrix_config, advance_config).await;
+			if let (Some(journal), Ok(value)) = (journal, &result) {
+				if let Err(err) = journal.add(value).await {
+					#[cfg(feature = "log")]
+					warn!("failed to write sticker to import journal: {err}");
+					#[cfg(not(feature = "log"))]
+					let _ = err; //fix unused warning
+				}
+			}
+			(sticker.positon, result)
+		});
 		let stickers = join_all(stickers_import_futures).await;
 
 		let mut ok_stickers = Vec::new();
 		let mut err_stickers = Vec::new();
-		for (i, sticker) in stickers.into_iter().enumerate() {
+		for (i, sticker) in stickers {
 			match sticker {
 				Ok(value) => ok_stickers.push(value),
 				Err(err) => err_stickers.push((i, err))
 			}
 		}
+		(ok_stickers, err_stickers)
+	}
 
+	/// Create the matrix pack of the imported stickers,
+	/// which is returned together with the failed stickers, if some imports have failed.
+	fn finish_import(
+		&self,
+		ok_stickers: Vec<matrix::sticker::Sticker>,
+		avatar: Option<matrix::sticker::Image>,
+		err_stickers: Vec<(usize, Error)>
+	) -> (matrix::stickerpack::StickerPack, Vec<(usize, Error)>) {
 		let stickerpack = matrix::stickerpack::StickerPack {
 			title: self.title.clone(),
 			id: format!("tg_name_{}", self.name),
-			tg_pack: Some((&self).to_owned().into()),
+			tg_pack: Some(self.into()),
+			avatar,
 			stickers: ok_stickers
 		};
 		#[cfg(feature = "log")]
 		if stickerpack.stickers.is_empty() {
 			warn!("imported pack {} is empty", self.name);
 		}
+		(stickerpack, err_stickers)
+	}
+
+	/// Match the stickers of a previously imported pack with the stickers of this pack by their telegram id.
+	fn match_stickers(&self, old_stickers: Vec<matrix::sticker::Sticker>) -> Merge {
+		let positions: HashMap<&str, usize> = self
+			.stickers
+			.iter()
+			.map(|sticker| (sticker.image().file_unique_id.as_str(), sticker.positon))
+			.collect();
+		let legacy = old_stickers.iter().all(|sticker| sticker.tg_sticker.is_none());
+		let mut merge = Merge {
+			stickers: Vec::new(),
+			missing: Vec::new(),
+			found: vec![false; self.stickers.len()],
+			diff: UpdateDiff::default()
+		};
+		for mut sticker in old_stickers {
+			let tg_sticker = match sticker.tg_sticker.as_mut() {
+				Some(tg_sticker) if tg_sticker.pack_name == self.name => tg_sticker,
+				// local sticker or sticker from another pack
+				Some(_) => {
+					merge.stickers.push(Some(sticker));
+					continue;
+				},
+				None if !legacy => {
+					merge.stickers.push(Some(sticker));
+					continue;
+				},
+				None => {
+					merge.missing.push(merge.stickers.len());
+					merge.stickers.push(Some(sticker));
+					continue;
+				}
+			};
+			match tg_sticker.bot_api_unique_id.as_deref().and_then(|id| positions.get(id)) {
+				Some(&position) => {
+					merge.found[position] = true;
+					tg_sticker.index = Some(position);
+					merge.diff.kept += 1;
+				},
+				None => merge.missing.push(merge.stickers.len())
+			}
+			merge.stickers.push(Some(sticker));
+		}
+		merge
+	}
+
+	/// Update a previously imported pack to the current state of this pack.
+	///
+	/// Only stickers, which are not already part of `old` are imported.
+	/// Stickers removed from the telegram pack are also removed from `old`.
+	/// Local changes of `old` like title, emoticons and order of the stickers are kept.
+	/// Stickers without telegram information are treated as local additions and are also kept.
+	/// Packs imported by older versions do not store the telegram id of the stickers.
+	/// In this case all stickers are imported again and compared by their url, so a database should be used.
+	///
+	/// Like [StickerPack::import] this function can partially fail.
+	/// Stickers, which have failed to import, are returned with their position at the telegram pack.
+	pub async fn update<'a, D>(
+		&self,
+		old: matrix::stickerpack::StickerPack,
+		tg_config: &Config,
+		matrix_config: &matrix::Config,
+		advance_config: &ImportConfig<'a, D>
+	) -> Result<
+		(matrix::stickerpack::StickerPack, UpdateDiff),
+		(matrix::stickerpack::StickerPack, UpdateDiff, Vec<(usize, Error)>)
+	>
+	where
+		D: Database + ?Sized
+	{
+		#[cfg(feature = "log")]
+		info!("update stickerpack {:?}({})", old.title, self.name);
+
+		let merge = self.match_stickers(old.stickers);
+		let new_stickers = self.stickers.iter().filter(|sticker| !merge.found[sticker.positon]);
+		let (imported, err_stickers) = self
+			.import_stickers(new_stickers, tg_config, matrix_config, advance_config)
+			.await;
+		let (stickers, diff) = merge.finish(imported, !err_stickers.is_empty());
+		// the avatar is only imported again, if telegram has changed the thumbnail of the pack
+		let avatar = match old.avatar {
+			Some(avatar) if !self.thumbnail_changed(old.tg_pack.as_ref()) => Some(avatar),
+			_ => self.import_avatar(&stickers, tg_config, matrix_config, advance_config).await
+		};
+		let stickerpack = matrix::stickerpack::StickerPack {
+			title: old.title,
+			id: old.id,
+			tg_pack: Some(self.into()),
+			avatar,
+			stickers
+		};
+		#[cfg(feature = "log")]
+		info!(
+			"updated stickerpack {}: {} added, {} removed, {} kept",
+			self.name,
+			diff.added.len(),
+			diff.removed.len(),
+			diff.kept
+		);
 		if err_stickers.is_empty() {
-			Ok(stickerpack)
+			Ok((stickerpack, diff))
 		} else {
-			Err((stickerpack, err_stickers))
+			Err((stickerpack, diff, err_stickers))
+		}
+	}
+}
+
+/// avatar used if the pack has no thumbnail or its import has failed: the thumbnail of the first sticker
+fn fallback_avatar(stickers: &[matrix::sticker::Sticker]) -> Option<matrix::sticker::Image> {
+	stickers
+		.first()
+		.map(|sticker| sticker.thumbnail.clone().unwrap_or_else(|| sticker.image.clone()))
+}
+
+/// State of [StickerPack::update] between matching the old stickers and importing the new ones.
+struct Merge {
+	/// stickers of the updated pack, `None` for removed stickers
+	stickers: Vec<Option<matrix::sticker::Sticker>>,
+	/// indices of old stickers from this pack, which could not be found by their id
+	missing: Vec<usize>,
+	/// stickers of the telegram pack by position, which are already part of the old pack
+	found: Vec<bool>,
+	diff: UpdateDiff
+}
+
+impl Merge {
+	/// Add the newly imported stickers and remove the stickers, which are no longer part of the telegram pack.
+	/// `import_failed` must be set, if the import of some stickers has failed.
+	fn finish(
+		self,
+		imported: Vec<matrix::sticker::Sticker>,
+		import_failed: bool
+	) -> (Vec<matrix::sticker::Sticker>, UpdateDiff) {
+		let Self {
+			mut stickers,
+			mut missing,
+			mut diff,
+			..
+		} = self;
+		for sticker in imported {
+			// stickers without id can only be found by their url
+			let old = missing
+				.iter()
+				.position(|&i| stickers[i].as_ref().is_some_and(|old| old.image.url == sticker.image.url));
+			if let Some(old) = old {
+				let old = stickers[missing.remove(old)].as_mut().unwrap();
+				old.tg_sticker = sticker.tg_sticker;
+				diff.kept += 1;
+			} else {
+				diff.added.push(sticker.clone());
+				stickers.push(Some(sticker));
+			}
 		}
+		for i in missing {
+			let has_id = stickers[i]
+				.as_ref()
+				.and_then(|sticker| sticker.tg_sticker.as_ref())
+				.is_some_and(|tg_sticker| tg_sticker.bot_api_unique_id.is_some());
+			// a sticker without id may only be missing, because its import has failed
+			if has_id || !import_failed {
+				diff.removed.extend(stickers[i].take());
+			}
+		}
+		(stickers.into_iter().flatten().collect(), diff)
 	}
 }
 
-/// Convert telegram stickerpack url to pack name.
-///
-/// The url must start with `https://t.me/addstickers/`, `t.me/addstickers/` or
-/// `tg://addstickers?set=`.
-pub fn pack_url_to_name(url: &str) -> Result<&str, InvalidPackUrl> {
-	url.strip_prefix("https://t.me/addstickers/")
-		.or_else(|| url.strip_prefix("t.me/addstickers/"))
-		.or_else(|| url.strip_prefix("tg://addstickers?set="))
-		.ok_or_else(|| InvalidPackUrl(url.to_owned()))
+/// Changes made by [StickerPack::update].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct UpdateDiff {
+	/// stickers, which were added to the telegram pack
+	pub added: Vec<matrix::sticker::Sticker>,
+	/// stickers, which were removed from the telegram pack
+	pub removed: Vec<matrix::sticker::Sticker>,
+	/// count of stickers, which are still part of the telegram pack
+	pub kept: usize
 }
 
 #[cfg(test)]
 mod tests {
 
-	use super::{ImportConfig, StickerPack};
-	use crate::{database::DummyDatabase, image::AnimationFormat};
+	use super::{ImportConfig, StickerPack, StickerType};
+	use crate::{
+		database::DummyDatabase,
+		image::AnimationFormat,
+		matrix::stickerpack::TgPackInfo,
+		test_util::{configs, sticker, tg_pack},
+		tg::MaskPoint
+	};
 	#[cfg(feature = "lottie")]
 	use lottieconv::Rgba;
 	use std::env;
 
 	async fn import(pack: &str, animation_format: Option<AnimationFormat>) {
-		let matrix_config = crate::matrix::Config {
-			homeserver_url: "none".to_owned(),
-			user: "none".to_owned(),
-			access_token: "none".to_owned()
-		};
+		let (matrix_config, _) = configs();
 		let tg_config = crate::tg::Config {
 			bot_key: env::var("TG_BOT_KEY").expect("environment variables TG_BOT_KEY is not set")
 		};
@@ -145,6 +455,199 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn deserialize_full() {
+		let pack: StickerPack = serde_json::from_str(
+			r#"{
+				"name": "masks_by_bot",
+				"title": "Masks",
+				"sticker_type": "mask",
+				"thumbnail": { "file_id": "thumb", "file_unique_id": "uthumb", "width": 100, "height": 100, "file_size": 512 },
+				"stickers": [{
+					"file_id": "id",
+					"file_unique_id": "uid",
+					"type": "mask",
+					"width": 512,
+					"height": 512,
+					"is_animated": false,
+					"is_video": false,
+					"set_name": "masks_by_bot",
+					"file_size": 2048,
+					"premium_animation": { "file_id": "premium", "file_unique_id": "upremium" },
+					"mask_position": { "point": "eyes", "x_shift": -1.0, "y_shift": 0.5, "scale": 2.0 }
+				}]
+			}"#
+		)
+		.unwrap();
+		assert_eq!(*pack.sticker_type(), StickerType::Mask);
+		assert_eq!(pack.thumbnail().as_ref().unwrap().file_size, Some(512));
+		let sticker = &pack.stickers()[0];
+		assert_eq!(sticker.set_name().as_deref(), Some("masks_by_bot"));
+		assert_eq!(sticker.image().file_size, Some(2048));
+		assert_eq!(sticker.premium_animation().as_ref().unwrap().file_id(), "premium");
+		let mask_position = sticker.mask_position().as_ref().unwrap();
+		assert_eq!(*mask_position.point(), MaskPoint::Eyes);
+		assert_eq!(*mask_position.scale(), 2.0);
+		assert!(!sticker.needs_repainting());
+	}
+
+	#[test]
+	fn update_merge() {
+		struct Case {
+			old: Vec<crate::matrix::sticker::Sticker>,
+			tg: &'static [&'static str],
+			/// stickers, which are expected to be imported, and the stickers returned by the import
+			import: &'static [&'static str],
+			imported: Vec<crate::matrix::sticker::Sticker>,
+			import_failed: bool,
+			stickers: &'static [&'static str],
+			added: &'static [&'static str],
+			removed: &'static [&'static str],
+			kept: usize,
+			/// new position of stickers, which were found by their id
+			positions: &'static [(&'static str, usize)]
+		}
+		let cases = [
+			// stickers are matched by id, local stickers and stickers of other packs are kept
+			Case {
+				old: vec![
+					sticker("a", Some(("cats", Some("a")))),
+					sticker("b", Some(("cats", Some("b")))),
+					sticker("local", None),
+					sticker("dog", Some(("dogs", Some("dog")))),
+				],
+				tg: &["c", "a"],
+				import: &["c"],
+				imported: vec![sticker("c", Some(("cats", Some("c"))))],
+				import_failed: false,
+				stickers: &["a", "local", "dog", "c"],
+				added: &["c"],
+				removed: &["b"],
+				kept: 1,
+				positions: &[("a", 1)]
+			},
+			// packs of older versions without ids are matched by url
+			Case {
+				old: vec![sticker("p", None), sticker("q", None)],
+				tg: &["p", "r"],
+				import: &["p", "r"],
+				imported: vec![
+					sticker("p", Some(("cats", Some("p")))),
+					sticker("r", Some(("cats", Some("r")))),
+				],
+				import_failed: false,
+				stickers: &["p", "r"],
+				added: &["r"],
+				removed: &["q"],
+				kept: 1,
+				positions: &[]
+			},
+			// stickers without id are kept, if they could be missing because of a failed import
+			Case {
+				old: vec![sticker("p", None), sticker("q", None)],
+				tg: &["p", "q"],
+				import: &["p", "q"],
+				imported: vec![sticker("p", Some(("cats", Some("p"))))],
+				import_failed: true,
+				stickers: &["p", "q"],
+				added: &[],
+				removed: &[],
+				kept: 1,
+				positions: &[]
+			},
+			// stickers with id are removed, even if the import has failed
+			Case {
+				old: vec![
+					sticker("a", Some(("cats", Some("a")))),
+					sticker("b", Some(("cats", Some("b")))),
+				],
+				tg: &["a", "c"],
+				import: &["c"],
+				imported: Vec::new(),
+				import_failed: true,
+				stickers: &["a"],
+				added: &[],
+				removed: &["b"],
+				kept: 1,
+				positions: &[("a", 0)]
+			}
+		];
+		for (i, case) in cases.into_iter().enumerate() {
+			let pack = tg_pack(case.tg);
+			let merge = pack.match_stickers(case.old);
+			let import: Vec<_> = pack
+				.stickers()
+				.iter()
+				.filter(|sticker| !merge.found[sticker.positon])
+				.map(|sticker| sticker.image().file_unique_id.as_str())
+				.collect();
+			assert_eq!(import, case.import, "case {i}");
+			let (stickers, diff) = merge.finish(case.imported, case.import_failed);
+			let bodies = |stickers: &[crate::matrix::sticker::Sticker]| -> Vec<String> {
+				stickers.iter().map(|sticker| sticker.body.clone()).collect()
+			};
+			assert_eq!(bodies(&stickers), case.stickers, "case {i}");
+			assert_eq!(bodies(&diff.added), case.added, "case {i}");
+			assert_eq!(bodies(&diff.removed), case.removed, "case {i}");
+			assert_eq!(diff.kept, case.kept, "case {i}");
+			for (body, position) in case.positions {
+				let sticker = stickers.iter().find(|sticker| sticker.body == *body).unwrap();
+				assert_eq!(sticker.tg_sticker.as_ref().unwrap().index, Some(*position), "case {i}");
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn avatar_fallback() {
+		let (matrix_config, tg_config) = configs();
+		let import_config = ImportConfig::<DummyDatabase> {
+			database: None,
+			dry_run: true,
+			..Default::default()
+		};
+		// the pack has no thumbnail, so the first sticker is used without downloading anything
+		let pack = tg_pack(&["a", "b"]);
+		let mut stickers = vec![sticker("first", None), sticker("second", None)];
+		let avatar = pack
+			.import_avatar(&stickers, &tg_config, &matrix_config, &import_config)
+			.await;
+		assert_eq!(avatar.unwrap().url.url(), "mxc://example.org/first");
+		stickers[0].thumbnail = Some(sticker("thumbnail", None).image);
+		let avatar = pack
+			.import_avatar(&stickers, &tg_config, &matrix_config, &import_config)
+			.await;
+		assert_eq!(avatar.unwrap().url.url(), "mxc://example.org/thumbnail");
+		assert!(pack
+			.import_avatar(&[], &tg_config, &matrix_config, &import_config)
+			.await
+			.is_none());
+	}
+
+	#[test]
+	fn avatar_thumbnail_changed() {
+		let tg_info = |thumbnail: Option<&str>| TgPackInfo {
+			name: "cats".to_owned(),
+			title: "Cats".to_owned(),
+			thumbnail_unique_id: thumbnail.map(ToOwned::to_owned)
+		};
+		let mut pack = tg_pack(&["a"]);
+		assert!(!pack.thumbnail_changed(None));
+		assert!(!pack.thumbnail_changed(Some(&tg_info(None))));
+		pack.thumbnail = Some(
+			serde_json::from_value(
+				serde_json::json!({ "file_id": "thumb", "file_unique_id": "uthumb", "width": 100, "height": 100 })
+			)
+			.unwrap()
+		);
+		assert!(!pack.thumbnail_changed(Some(&tg_info(Some("uthumb")))));
+		assert!(pack.thumbnail_changed(Some(&tg_info(Some("uold")))));
+		// packs imported before the thumbnail was stored
+		assert!(pack.thumbnail_changed(Some(&tg_info(None))));
+		assert!(pack.thumbnail_changed(None));
+		// the stored thumbnail id is updated together with the avatar
+		assert_eq!(TgPackInfo::from(&pack).thumbnail_unique_id.as_deref(), Some("uthumb"));
+	}
+
 	#[tokio::test]
 	#[ignore]
 	async fn import_simple() {
@@ -1,16 +1,21 @@
-//! This module deals with translating telegram's video stickers to webp animations.
+//! This module deals with translating telegram's video stickers to webp animations
+//! and with encoding stickers for telegram and whatsapp.
 
 use crate::error::Error;
 use ffmpeg::{
-	codec::Context as CodecContext,
-	decoder,
+	codec::{self, Context as CodecContext},
+	decoder, encoder,
 	format::{self, Pixel},
 	media::Type,
 	software::scaling::{context::Context as ScalingContext, flag::Flags},
-	util::frame::video::Video
+	util::frame::video::Video,
+	Dictionary, Packet, Rational
 };
-use std::path::Path;
-use webp_animation::{Encoder, WebPData};
+use std::{io::Read, path::Path};
+use webp_animation::{Encoder, EncoderOptions, EncodingConfig, WebPData};
+
+#[cfg(feature = "log")]
+use log::info;
 
 pub(crate) fn webm2webp<P: AsRef<Path>>(file: &P) -> Result<(WebPData, u32, u32), Error> {
 	// heavily inspired by
@@ -61,3 +66,290 @@ pub(crate) fn webm2webp<P: AsRef<Path>>(file: &P) -> Result<(WebPData, u32, u32)
 	let webp = encoder.finalize(timestamp)?;
 	Ok((webp, decoder.width(), decoder.height()))
 }
+
+/// decoded frames of an image or video
+pub(crate) struct Frames {
+	pub(crate) width: u32,
+	pub(crate) height: u32,
+	/// tightly packed RGBA data of each frame and its timestamp in milliseconds
+	pub(crate) frames: Vec<(Vec<u8>, i64)>
+}
+
+/// decode all frames of a video, gif or image file to RGBA
+pub(crate) fn decode_frames<P: AsRef<Path>>(file: &P) -> Result<Frames, Error> {
+	let mut ictx = format::input(file)?;
+	let input = ictx.streams().best(Type::Video).ok_or(ffmpeg::Error::StreamNotFound)?;
+
+	let video_stream_index = input.index();
+	let time_base = input.time_base();
+	let ctx_decoder = CodecContext::from_parameters(input.parameters())?;
+	let mut decoder = ctx_decoder.decoder().video()?;
+	let (width, height) = (decoder.width(), decoder.height());
+
+	let mut scaler = ScalingContext::get(decoder.format(), width, height, Pixel::RGBA, width, height, Flags::BILINEAR)?;
+
+	let mut frames: Vec<(Vec<u8>, i64)> = Vec::new();
+	let mut receive_and_process_decoded_frames = |decoder: &mut decoder::Video| -> Result<(), Error> {
+		let mut decoded = Video::empty();
+		while decoder.receive_frame(&mut decoded).is_ok() {
+			let mut rgba_frame = Video::empty();
+			scaler.run(&decoded, &mut rgba_frame)?;
+			// the lines of the frame can be padded
+			let line_size = width as usize * 4;
+			let data = rgba_frame
+				.data(0)
+				.chunks(rgba_frame.stride(0))
+				.take(height as usize)
+				.flat_map(|line| &line[..line_size])
+				.copied()
+				.collect();
+			let timestamp = match decoded.timestamp() {
+				Some(timestamp) => timestamp * time_base.numerator() as i64 * 1000 / time_base.denominator() as i64,
+				None => frames.last().map(|(_, timestamp)| timestamp + 40).unwrap_or(0)
+			};
+			frames.push((data, timestamp));
+		}
+		Ok(())
+	};
+
+	for (stream, packet) in ictx.packets() {
+		if stream.index() == video_stream_index {
+			decoder.send_packet(&packet)?;
+			receive_and_process_decoded_frames(&mut decoder)?;
+		}
+	}
+	decoder.send_eof()?;
+	receive_and_process_decoded_frames(&mut decoder)?;
+
+	// videos must not start at timestamp 0
+	let start = frames.first().map(|(_, timestamp)| *timestamp).unwrap_or(0);
+	for (_, timestamp) in &mut frames {
+		*timestamp -= start;
+	}
+	Ok(Frames { width, height, frames })
+}
+
+/// decode all frames of a static or animated webp image
+pub(crate) fn decode_webp(data: &[u8]) -> Result<Frames, Error> {
+	let decoder = webp_animation::Decoder::new(data)?;
+	let (width, height) = decoder.dimensions();
+	let mut frames = Vec::new();
+	// the timestamp of a webp frame is the time when the frame ends
+	let mut start = 0;
+	for frame in decoder {
+		frames.push((frame.data().to_vec(), start));
+		start = frame.timestamp() as i64;
+	}
+	Ok(Frames { width, height, frames })
+}
+
+/// copy tightly packed RGBA data to a ffmpeg frame
+fn rgba_frame(data: &[u8], width: u32, height: u32) -> Video {
+	let mut frame = Video::new(Pixel::RGBA, width, height);
+	let stride = frame.stride(0);
+	let line_size = width as usize * 4;
+	for (line, src) in frame.data_mut(0).chunks_mut(stride).zip(data.chunks(line_size)) {
+		line[..line_size].copy_from_slice(src);
+	}
+	frame
+}
+
+/// encode the first frame as png with the size `(width, height)`
+pub(crate) fn encode_png(frames: &Frames, (width, height): (u32, u32)) -> Result<Vec<u8>, Error> {
+	let (data, _) = frames.frames.first().ok_or(ffmpeg::Error::InvalidData)?;
+	let codec = encoder::find(codec::Id::PNG).ok_or(ffmpeg::Error::EncoderNotFound)?;
+	let mut encoder = CodecContext::new_with_codec(codec).encoder().video()?;
+	encoder.set_width(width);
+	encoder.set_height(height);
+	encoder.set_format(Pixel::RGBA);
+	encoder.set_time_base(Rational(1, 25));
+	let mut encoder = encoder.open()?;
+
+	let mut scaler = ScalingContext::get(
+		Pixel::RGBA,
+		frames.width,
+		frames.height,
+		Pixel::RGBA,
+		width,
+		height,
+		Flags::BICUBIC
+	)?;
+	let mut scaled = Video::empty();
+	scaler.run(&rgba_frame(data, frames.width, frames.height), &mut scaled)?;
+	scaled.set_pts(Some(0));
+	encoder.send_frame(&scaled)?;
+	encoder.send_eof()?;
+
+	let mut png = Vec::new();
+	let mut packet = Packet::empty();
+	while encoder.receive_packet(&mut packet).is_ok() {
+		png.extend_from_slice(packet.data().unwrap_or_default());
+	}
+	Ok(png)
+}
+
+/// Encode the frames as vp9 webm video with the size `(width, height)`.
+/// Frames with a timestamp after `max_duration` (in milliseconds) are dropped,
+/// as well as frames, which would exceed 30 fps.
+/// `bit_rate` is the target bit rate in bits per second.
+pub(crate) fn encode_vp9(
+	frames: &Frames,
+	(width, height): (u32, u32),
+	max_duration: i64,
+	bit_rate: usize
+) -> Result<Vec<u8>, Error> {
+	let tmp = tempfile::Builder::new().suffix(".webm").tempfile()?;
+	let mut octx = format::output_as(tmp.path(), "webm")?;
+	let global_header = octx.format().flags().contains(format::Flags::GLOBAL_HEADER);
+	let codec = encoder::find_by_name("libvpx-vp9")
+		.or_else(|| encoder::find(codec::Id::VP9))
+		.ok_or(ffmpeg::Error::EncoderNotFound)?;
+	let mut ost = octx.add_stream(codec)?;
+	let mut encoder = CodecContext::new_with_codec(codec).encoder().video()?;
+	encoder.set_width(width);
+	encoder.set_height(height);
+	encoder.set_format(Pixel::YUVA420P);
+	encoder.set_time_base(Rational(1, 1000));
+	encoder.set_frame_rate(Some(Rational(30, 1)));
+	encoder.set_bit_rate(bit_rate);
+	if global_header {
+		encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+	}
+	let mut options = Dictionary::new();
+	options.set("deadline", "good");
+	let mut encoder = encoder.open_with(options)?;
+	ost.set_parameters(&encoder);
+	octx.write_header()?;
+	let ost_time_base = octx.stream(0).ok_or(ffmpeg::Error::StreamNotFound)?.time_base();
+
+	let mut scaler = ScalingContext::get(
+		Pixel::RGBA,
+		frames.width,
+		frames.height,
+		Pixel::YUVA420P,
+		width,
+		height,
+		Flags::BICUBIC
+	)?;
+	let mut receive_and_process_encoded_packets =
+		|encoder: &mut encoder::Video, octx: &mut format::context::Output| -> Result<(), Error> {
+			let mut packet = Packet::empty();
+			while encoder.receive_packet(&mut packet).is_ok() {
+				packet.set_stream(0);
+				packet.rescale_ts(Rational(1, 1000), ost_time_base);
+				packet.write_interleaved(octx)?;
+			}
+			Ok(())
+		};
+
+	let mut last_timestamp = None;
+	for (data, timestamp) in &frames.frames {
+		if *timestamp >= max_duration {
+			break;
+		}
+		if last_timestamp.is_some_and(|last| timestamp - last < 1000 / 30) {
+			continue;
+		}
+		last_timestamp = Some(*timestamp);
+		let mut scaled = Video::empty();
+		scaler.run(&rgba_frame(data, frames.width, frames.height), &mut scaled)?;
+		scaled.set_pts(Some(*timestamp));
+		encoder.send_frame(&scaled)?;
+		receive_and_process_encoded_packets(&mut encoder, &mut octx)?;
+	}
+	encoder.send_eof()?;
+	receive_and_process_encoded_packets(&mut encoder, &mut octx)?;
+	octx.write_trailer()?;
+	drop(octx);
+
+	let mut webm = Vec::new();
+	std::fs::File::open(tmp.path())?.read_to_end(&mut webm)?;
+	Ok(webm)
+}
+
+/// Encode the frames by [encode_vp9], lowering the bit rate until the video is not larger than `max_size` bytes.
+/// Return `None` if the video is still too large at the lowest bit rate.
+pub(crate) fn encode_vp9_max_size(
+	frames: &Frames,
+	size: (u32, u32),
+	max_duration: i64,
+	max_size: usize
+) -> Result<Option<Vec<u8>>, Error> {
+	for bit_rate in [400_000, 250_000, 150_000, 100_000] {
+		let webm = encode_vp9(frames, size, max_duration, bit_rate)?;
+		if webm.len() <= max_size {
+			return Ok(Some(webm));
+		}
+		#[cfg(feature = "log")]
+		info!(
+			"  video has {} bytes at {bit_rate} bit/s, retry with lower bit rate",
+			webm.len()
+		);
+	}
+	Ok(None)
+}
+
+/// Scale the frames to fit into a square of `size` pixels, keeping the aspect ratio.
+/// The remaining space is transparent.
+pub(crate) fn fit_square(frames: &Frames, size: u32) -> Result<Frames, Error> {
+	let (width, height) = if frames.width >= frames.height {
+		(size, (frames.height * size / frames.width.max(1)).max(1))
+	} else {
+		((frames.width * size / frames.height).max(1), size)
+	};
+	let mut scaler = ScalingContext::get(
+		Pixel::RGBA,
+		frames.width,
+		frames.height,
+		Pixel::RGBA,
+		width,
+		height,
+		Flags::BICUBIC
+	)?;
+	let line_size = width as usize * 4;
+	let square_line_size = size as usize * 4;
+	// center the scaled frame
+	let offset = (size - height) as usize / 2 * square_line_size + (size - width) as usize / 2 * 4;
+	let mut fitted = Vec::with_capacity(frames.frames.len());
+	for (data, timestamp) in &frames.frames {
+		let mut scaled = Video::empty();
+		scaler.run(&rgba_frame(data, frames.width, frames.height), &mut scaled)?;
+		let mut square = vec![0; square_line_size * size as usize];
+		let stride = scaled.stride(0);
+		for (i, line) in scaled.data(0).chunks(stride).take(height as usize).enumerate() {
+			let start = offset + i * square_line_size;
+			square[start..start + line_size].copy_from_slice(&line[..line_size]);
+		}
+		fitted.push((square, *timestamp));
+	}
+	Ok(Frames {
+		width: size,
+		height: size,
+		frames: fitted
+	})
+}
+
+/// Encode the frames as lossy webp with the given `quality` between 0 and 100.
+/// Only every `step`-th frame is kept, to reduce the size of animations.
+/// Frames with a timestamp after `max_duration` (in milliseconds) are dropped.
+pub(crate) fn encode_webp(frames: &Frames, quality: f32, step: usize, max_duration: i64) -> Result<Vec<u8>, Error> {
+	let mut encoder = Encoder::new_with_options((frames.width, frames.height), EncoderOptions {
+		encoding_config: Some(EncodingConfig::new_lossy(quality)),
+		..Default::default()
+	})?;
+	let frames: Vec<_> = frames
+		.frames
+		.iter()
+		.filter(|(_, timestamp)| *timestamp < max_duration)
+		.step_by(step.max(1))
+		.collect();
+	// the last frame is shown as long as the frames before on average
+	let duration = match (frames.first(), frames.last()) {
+		(Some((_, first)), Some((_, last))) if frames.len() > 1 => last + (last - first) / (frames.len() as i64 - 1),
+		_ => 100
+	};
+	for (data, timestamp) in &frames {
+		encoder.add_frame(data, *timestamp as i32)?;
+	}
+	Ok(encoder.finalize(duration as i32)?.to_vec())
+}